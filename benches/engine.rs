@@ -0,0 +1,65 @@
+use caesar_cipher::alphabets::{Alphabet, AsciiLowerCaseAlphabet, IncompleteAscii};
+use caesar_cipher::{CaesarEngine, ClearText, Shift};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const MESSAGE_SIZE: usize = 1 << 20;
+
+/// Builds a message of `MESSAGE_SIZE` letters cycling over the alphabet
+fn message<A: Alphabet>() -> String {
+    A::letters().iter().cycle().take(MESSAGE_SIZE).collect()
+}
+
+/// Encryption as it was done before the lookup tables: a linear scan of
+/// the alphabet for every letter of the message, kept as a baseline.
+fn linear_scan_encrypt<A: Alphabet>(shifted_alphabet: &[char], message: &str) -> String {
+    let alphabet = A::letters();
+    message
+        .chars()
+        .map(|letter| {
+            let index = alphabet.iter().position(|l| *l == letter).unwrap();
+            shifted_alphabet[index]
+        })
+        .collect()
+}
+
+fn bench_alphabet<A: Alphabet>(c: &mut Criterion, name: &str) {
+    let message = message::<A>();
+    let clear_text = ClearText::<A>::try_new(&message).unwrap();
+    let engine = CaesarEngine::<A>::new(Shift(3));
+    let cipher_text = engine.encrypt(&clear_text);
+
+    let mut shifted_alphabet = A::letters().to_vec();
+    shifted_alphabet.rotate_left(3);
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(message.len() as u64));
+    group.bench_function(BenchmarkId::new("validate", "lookup-table"), |b| {
+        b.iter(|| ClearText::<A>::try_new(black_box(&message)))
+    });
+    group.bench_function(BenchmarkId::new("encrypt", "lookup-table"), |b| {
+        b.iter(|| engine.encrypt(black_box(&clear_text)))
+    });
+    group.bench_function(BenchmarkId::new("encrypt", "linear-scan"), |b| {
+        b.iter(|| linear_scan_encrypt::<A>(&shifted_alphabet, black_box(&message)))
+    });
+    group.bench_function(BenchmarkId::new("decrypt", "lookup-table"), |b| {
+        b.iter(|| engine.decrypt(black_box(&cipher_text)))
+    });
+    let mut output = String::new();
+    group.bench_function(BenchmarkId::new("encrypt", "reused-buffer"), |b| {
+        b.iter(|| engine.encrypt_into(black_box(&clear_text), &mut output))
+    });
+    let mut bytes = message.clone().into_bytes();
+    group.bench_function(BenchmarkId::new("encrypt", "ascii-bytes"), |b| {
+        b.iter(|| engine.encrypt_bytes(black_box(&mut bytes)))
+    });
+    group.finish();
+}
+
+fn engine_benchmarks(c: &mut Criterion) {
+    bench_alphabet::<AsciiLowerCaseAlphabet>(c, "ascii-lowercase");
+    bench_alphabet::<IncompleteAscii>(c, "incomplete-ascii");
+}
+
+criterion_group!(benches, engine_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,408 @@
+//! Procedural macros for the `caesar-cipher` crate
+//!
+//! [caesar!] encrypts a string literal at compile time, so that only the
+//! encrypted text ends up in the binary.
+//!
+//! [derive@Alphabet] implements the `Alphabet` trait, checking the letters at compile time.
+use caesar_cipher::alphabets::{
+    validate_letters, Alphabet, ArabicAlphabet, AsciiLowerCaseAlphabet, GreekAlphabet,
+    HebrewAlphabet, IncompleteAscii, InvalidAlphabet, RussianAlphabet,
+};
+use caesar_cipher::{CaesarEngine, ClearText, Shift};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, DeriveInput, Expr, ExprLit, ExprRange, ExprUnary, Ident, Lit, LitStr, Meta,
+    Path, RangeLimits, Token, UnOp,
+};
+
+/// Arguments of [caesar!]: `"text", shift = N, alphabet = Path`
+struct CaesarInput {
+    text: LitStr,
+    shift: isize,
+    alphabet: Path,
+}
+
+impl Parse for CaesarInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let text = input.parse::<LitStr>()?;
+        let mut shift = None;
+        let mut alphabet = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            match name.to_string().as_str() {
+                "shift" if shift.is_none() => shift = Some(parse_shift(&input.parse()?)?),
+                "alphabet" if alphabet.is_none() => alphabet = Some(input.parse::<Path>()?),
+                "shift" | "alphabet" => {
+                    return Err(syn::Error::new(name.span(), "duplicate argument"))
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "unknown argument, expected `shift` or `alphabet`",
+                    ))
+                }
+            }
+        }
+
+        let missing = |argument| syn::Error::new(text.span(), format!("missing `{argument}`"));
+        Ok(Self {
+            shift: shift.ok_or_else(|| missing("shift = ..."))?,
+            alphabet: alphabet.ok_or_else(|| missing("alphabet = ..."))?,
+            text,
+        })
+    }
+}
+
+/// Reads an integer literal, possibly negative
+fn parse_shift(expr: &Expr) -> syn::Result<isize> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(shift),
+            ..
+        }) => shift.base10_parse(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => parse_shift(expr).map(|shift| -shift),
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "the shift must be an integer literal",
+        )),
+    }
+}
+
+fn encrypt<A: Alphabet>(input: &CaesarInput) -> syn::Result<String> {
+    let message = ClearText::<A>::try_new(input.text.value())
+        .map_err(|error| syn::Error::new(input.text.span(), error))?;
+    let engine = CaesarEngine::<A>::new(Shift(input.shift));
+    Ok(engine.encrypt(&message).as_ref().clone())
+}
+
+/// Encrypts a string literal with the Caesar cipher at compile time
+///
+/// The macro takes the text, the shift and the alphabet, which must be one of the
+/// alphabets of `caesar_cipher::alphabets`, and expands to a `CipherText` of this
+/// alphabet. A character of the text that is not in the alphabet is a compile error.
+///
+/// The letters of the alphabet are needed when the macro runs, before the types of
+/// the program are known, so other alphabets, including those implemented with
+/// [derive@Alphabet], are not supported. The alphabet is named by a path ending with
+/// the name of the alphabet, and this path must lead to the alphabet of the crate:
+/// another type with the same name does not compile.
+///
+/// A `CipherText` owns its text in a `String`, which cannot be built in a `const`, so
+/// the expansion is an expression creating the `CipherText` when it is evaluated. Only
+/// the encrypted text is stored in the binary, in a `const`.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+/// use caesar_cipher::{CaesarEngine, Shift};
+/// use caesar_cipher_macros::caesar;
+///
+/// let encrypted = caesar!("Ave Imperator", shift = 3, alphabet = IncompleteAscii);
+/// assert_eq!(&encrypted, "Dyh'Lpshudwru");
+///
+/// let engine = CaesarEngine::<IncompleteAscii>::new(Shift(3));
+/// assert_eq!(&engine.decrypt(&encrypted), "Ave Imperator");
+///
+/// let encrypted = caesar!("abc", shift = -1, alphabet = AsciiLowerCaseAlphabet);
+/// assert_eq!(&encrypted, "zab");
+/// ```
+///
+/// A text that is not in the alphabet does not compile
+/// ```compile_fail
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher_macros::caesar;
+///
+/// let encrypted = caesar!("Ave", shift = -1, alphabet = AsciiLowerCaseAlphabet);
+/// ```
+///
+/// Neither does an alphabet that only has the name of one of the alphabets of the crate
+/// ```compile_fail
+/// use caesar_cipher_macros::caesar;
+///
+/// mod mine {
+///     #[derive(Debug, caesar_cipher_macros::Alphabet)]
+///     #[letters = "zyxwvutsrqponmlkjihgfedcba"]
+///     pub struct AsciiLowerCaseAlphabet;
+/// }
+///
+/// let encrypted = caesar!("abc", shift = 1, alphabet = mine::AsciiLowerCaseAlphabet);
+/// ```
+#[proc_macro]
+pub fn caesar(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as CaesarInput);
+    let alphabet = &input.alphabet;
+    let Some(name) = alphabet.segments.last().map(|segment| &segment.ident) else {
+        return syn::Error::new_spanned(alphabet, "expected an alphabet")
+            .to_compile_error()
+            .into();
+    };
+
+    let encrypted = match name.to_string().as_str() {
+        "AsciiLowerCaseAlphabet" => encrypt::<AsciiLowerCaseAlphabet>(&input),
+        "IncompleteAscii" => encrypt::<IncompleteAscii>(&input),
+        "GreekAlphabet" => encrypt::<GreekAlphabet>(&input),
+        "RussianAlphabet" => encrypt::<RussianAlphabet>(&input),
+        "HebrewAlphabet" => encrypt::<HebrewAlphabet>(&input),
+        "ArabicAlphabet" => encrypt::<ArabicAlphabet>(&input),
+        _ => Err(syn::Error::new_spanned(
+            alphabet,
+            "unknown alphabet, expected one of the alphabets of `caesar_cipher::alphabets`",
+        )),
+    };
+
+    match encrypted {
+        // The text was encrypted with the alphabet of the crate, the constant only
+        // has a type if the given path leads to the same alphabet
+        Ok(encrypted) => quote! {
+            {
+                const _: ::core::marker::PhantomData<#alphabet> =
+                    ::core::marker::PhantomData::<::caesar_cipher::alphabets::#name>;
+                const ENCRYPTED: &str = #encrypted;
+                ::caesar_cipher::CipherText::<#alphabet>::try_new(ENCRYPTED)
+                    .expect("the text is checked by `caesar!`")
+            }
+        }
+        .into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Reads the letters given by an element of `#[letters(...)]`:
+/// a string, a character, or a range of characters
+fn parse_letters(expr: &Expr, letters: &mut Vec<char>) -> syn::Result<()> {
+    let char_bound = |bound: &Option<Box<Expr>>| match bound.as_deref() {
+        Some(Expr::Lit(ExprLit {
+            lit: Lit::Char(c), ..
+        })) => Ok(c.value()),
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "expected a range between two character literals, like 'a'..='z'",
+        )),
+    };
+
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(string),
+            ..
+        }) => letters.extend(string.value().chars()),
+        Expr::Lit(ExprLit {
+            lit: Lit::Char(c), ..
+        }) => letters.push(c.value()),
+        Expr::Range(ExprRange {
+            start, limits, end, ..
+        }) => {
+            let (start, end) = (char_bound(start)?, char_bound(end)?);
+            match limits {
+                RangeLimits::Closed(_) => letters.extend(start..=end),
+                RangeLimits::HalfOpen(_) => letters.extend(start..end),
+            }
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "expected a string, a character or a range of characters",
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Implements the `Alphabet` trait, with the letters given by the `letters` attribute
+///
+/// The letters are given either as a string, with `#[letters = "..."]`, or as a list
+/// of strings, characters and ranges of characters, like `#[letters('a'..='z', ' ')]`,
+/// whose letters are concatenated in order.
+///
+/// An alphabet with no letters or with a letter appearing twice is a compile error.
+/// Alphabets implemented by hand can be checked with
+/// `caesar_cipher::alphabets::validate_alphabet`.
+///
+/// The name of the alphabet, which is stored with serialized texts and in key files,
+/// is given by the optional `#[alphabet_name = "..."]` attribute. It defaults to the
+/// path of the type, with its crate and modules, so that two alphabets with the same
+/// type name in different modules do not read each other's texts and keys; moving or
+/// renaming the type then changes its name.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::Alphabet;
+/// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+/// use caesar_cipher_macros::Alphabet;
+///
+/// #[derive(Debug, Alphabet)]
+/// #[letters('a'..='z', 'A'..='Z', "0123456789")]
+/// struct AlphaNumeric;
+///
+/// #[derive(Debug, Alphabet)]
+/// #[letters = "aeiou"]
+/// #[alphabet_name = "vowels"]
+/// struct Vowels;
+///
+/// assert_eq!(AlphaNumeric::letters().len(), 62);
+/// assert_eq!(AlphaNumeric::name(), concat!(module_path!(), "::AlphaNumeric"));
+/// assert_eq!(Vowels::letters(), &['a', 'e', 'i', 'o', 'u']);
+/// assert_eq!(Vowels::name(), "vowels");
+///
+/// let message = ClearText::<AlphaNumeric>::try_new("Zebra42").unwrap();
+/// let engine = CaesarEngine::<AlphaNumeric>::new(Shift(1));
+/// assert_eq!(&engine.encrypt(&message), "0fcsb53");
+/// ```
+///
+/// A letter appearing twice does not compile
+/// ```compile_fail
+/// use caesar_cipher_macros::Alphabet;
+///
+/// #[derive(Debug, Alphabet)]
+/// #[letters('a'..='z', "aeiou")]
+/// struct Overlapping;
+/// ```
+///
+/// Alphabets with the same type name in different modules have different names
+/// ```
+/// use caesar_cipher::alphabets::Alphabet;
+///
+/// mod latin {
+///     #[derive(Debug, caesar_cipher_macros::Alphabet)]
+///     #[letters('a'..='z')]
+///     pub struct Letters;
+/// }
+///
+/// mod reversed {
+///     #[derive(Debug, caesar_cipher_macros::Alphabet)]
+///     #[letters = "zyxwvutsrqponmlkjihgfedcba"]
+///     pub struct Letters;
+/// }
+///
+/// assert_ne!(latin::Letters::name(), reversed::Letters::name());
+/// assert!(latin::Letters::name().ends_with("::latin::Letters"));
+/// ```
+#[proc_macro_derive(Alphabet, attributes(letters, alphabet_name))]
+pub fn derive_alphabet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match alphabet_letters(&input).and_then(|letters| Ok((letters, alphabet_name(&input)?))) {
+        Ok((letters, alphabet_name)) => {
+            let name = &input.ident;
+            let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+            let len = letters.len();
+            let alphabet_name = match alphabet_name {
+                Some(alphabet_name) => quote!(#alphabet_name),
+                None => quote!(::core::concat!(
+                    ::core::module_path!(),
+                    "::",
+                    ::core::stringify!(#name)
+                )),
+            };
+            quote! {
+                impl #impl_generics ::caesar_cipher::alphabets::Alphabet
+                    for #name #type_generics #where_clause
+                {
+                    fn letters() -> &'static [char] {
+                        const LETTERS: [char; #len] = [#(#letters),*];
+
+                        &LETTERS
+                    }
+
+                    fn name() -> &'static str {
+                        #alphabet_name
+                    }
+                }
+            }
+            .into()
+        }
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Reads the name given by the `alphabet_name` attribute, if any
+fn alphabet_name(input: &DeriveInput) -> syn::Result<Option<String>> {
+    let mut attributes = input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("alphabet_name"));
+    let Some(attribute) = attributes.next() else {
+        return Ok(None);
+    };
+    if let Some(duplicate) = attributes.next() {
+        return Err(syn::Error::new_spanned(
+            duplicate,
+            "duplicate `alphabet_name` attribute",
+        ));
+    }
+
+    match &attribute.meta {
+        Meta::NameValue(name_value) => match &name_value.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(name),
+                ..
+            }) => Ok(Some(name.value())),
+            value => Err(syn::Error::new_spanned(value, "expected a string")),
+        },
+        _ => Err(syn::Error::new_spanned(
+            attribute,
+            "expected `#[alphabet_name = \"...\"]`",
+        )),
+    }
+}
+
+/// Reads and checks the letters given by the `letters` attribute
+fn alphabet_letters(input: &DeriveInput) -> syn::Result<Vec<char>> {
+    let mut attributes = input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("letters"));
+    let Some(attribute) = attributes.next() else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "missing `#[letters = \"...\"]` or `#[letters(...)]` attribute",
+        ));
+    };
+    if let Some(duplicate) = attributes.next() {
+        return Err(syn::Error::new_spanned(
+            duplicate,
+            "duplicate `letters` attribute",
+        ));
+    }
+
+    let mut letters = Vec::new();
+    match &attribute.meta {
+        Meta::NameValue(name_value) => parse_letters(&name_value.value, &mut letters)?,
+        Meta::List(list) => {
+            let elements = list.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+            for element in &elements {
+                parse_letters(element, &mut letters)?;
+            }
+        }
+        Meta::Path(_) => {
+            return Err(syn::Error::new_spanned(
+                attribute,
+                "expected `#[letters = \"...\"]` or `#[letters(...)]`",
+            ))
+        }
+    }
+
+    match validate_letters(&letters) {
+        Ok(_) => Ok(letters),
+        Err(InvalidAlphabet::Empty) => Err(syn::Error::new_spanned(
+            attribute,
+            "the alphabet has no letters",
+        )),
+        Err(InvalidAlphabet::DuplicateLetter(letter)) => Err(syn::Error::new_spanned(
+            attribute,
+            format!("the letter {letter:?} appears more than once in the alphabet"),
+        )),
+    }
+}
@@ -0,0 +1,215 @@
+//! The [affine cipher](https://en.wikipedia.org/wiki/Affine_cipher)
+//!
+//! The affine cipher generalizes the Caesar cipher: the letter at position `x` in
+//! the alphabet is replaced by the letter at position `(a * x + b) mod m`, where `m`
+//! is the number of letters of the alphabet. The Caesar cipher is the affine cipher
+//! with `a = 1`.
+//!
+//! Decryption needs the inverse of `a` modulo `m`, so `a` must be coprime with `m`.
+use alloc::vec::Vec;
+
+use crate::alphabets::Alphabet;
+use crate::table::Substitution;
+use crate::{CharacterNotInAlphabet, Cipher, CipherText, ClearText, ForeignCharPolicy};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum InvalidAffineKey {
+    /// The multiplier has no inverse modulo the number of letters of the alphabet
+    NotCoprime { a: usize, alphabet_len: usize },
+}
+
+/// Struct that encrypts and decrypts message with the affine cipher.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::affine::{AffineEngine, InvalidAffineKey};
+/// use caesar_cipher::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+/// use caesar_cipher::ClearText;
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("affinecipher").unwrap();
+///
+/// let engine = AffineEngine::<AsciiLowerCaseAlphabet>::try_new(5, 8).unwrap();
+/// assert_eq!(engine.decryption_key(), (21, 8));
+///
+/// let encrypted_message = engine.encrypt(&message);
+/// assert_eq!(&encrypted_message, "ihhwvcswfrcp");
+/// assert_eq!(engine.decrypt(&encrypted_message), message);
+///
+/// // IncompleteAscii has 60 letters, and 5 divides 60
+/// assert_eq!(
+///     AffineEngine::<IncompleteAscii>::try_new(5, 8).err(),
+///     Some(InvalidAffineKey::NotCoprime { a: 5, alphabet_len: 60 })
+/// );
+/// ```
+pub struct AffineEngine<A: Alphabet> {
+    _marker: core::marker::PhantomData<A>,
+    a: usize,
+    a_inverse: usize,
+    b: usize,
+    substitution: Substitution,
+}
+
+impl<A: Alphabet> AffineEngine<A> {
+    /// Creates a new engine for the key `(a, b)`
+    ///
+    /// Both numbers are taken modulo the number of letters of the alphabet.
+    pub fn try_new(a: usize, b: usize) -> Result<Self, InvalidAffineKey> {
+        let alphabet = A::letters();
+        let alphabet_len = alphabet.len();
+        let (a, b) = (a % alphabet_len, b % alphabet_len);
+        let a_inverse = modular_inverse(a, alphabet_len)
+            .ok_or(InvalidAffineKey::NotCoprime { a, alphabet_len })?;
+
+        let substituted = (0..alphabet_len)
+            .map(|x| alphabet[(a * x + b) % alphabet_len])
+            .collect::<Vec<_>>();
+        Ok(Self {
+            _marker: Default::default(),
+            a,
+            a_inverse,
+            b,
+            substitution: Substitution::new(alphabet, &substituted),
+        })
+    }
+
+    /// The `(a, b)` key used for encryption
+    pub fn encryption_key(&self) -> (usize, usize) {
+        (self.a, self.b)
+    }
+
+    /// The `(a⁻¹, b)` key used for decryption, the letter at position `y`
+    /// is decrypted to the letter at position `a⁻¹ * (y - b) mod m`
+    pub fn decryption_key(&self) -> (usize, usize) {
+        (self.a_inverse, self.b)
+    }
+
+    /// Encrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
+    pub fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        CipherText {
+            _marker: Default::default(),
+            cipher: self
+                .substitution
+                .encrypt(&clear_message.message, ForeignCharPolicy::Passthrough)
+                .expect("passing characters through cannot fail"),
+        }
+    }
+
+    /// Decrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
+    pub fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        ClearText {
+            _marker: Default::default(),
+            message: self
+                .substitution
+                .decrypt(&cipher_message.cipher, ForeignCharPolicy::Passthrough)
+                .expect("passing characters through cannot fail"),
+        }
+    }
+
+    /// Encrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_encrypt(
+        &self,
+        clear_message: &ClearText<A>,
+    ) -> Result<CipherText<A>, CharacterNotInAlphabet> {
+        Ok(CipherText {
+            _marker: Default::default(),
+            cipher: self
+                .substitution
+                .encrypt(&clear_message.message, ForeignCharPolicy::Reject)?,
+        })
+    }
+
+    /// Decrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_decrypt(
+        &self,
+        cipher_message: &CipherText<A>,
+    ) -> Result<ClearText<A>, CharacterNotInAlphabet> {
+        Ok(ClearText {
+            _marker: Default::default(),
+            message: self
+                .substitution
+                .decrypt(&cipher_message.cipher, ForeignCharPolicy::Reject)?,
+        })
+    }
+}
+
+impl<A: Alphabet> Cipher<A> for AffineEngine<A> {
+    fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        AffineEngine::encrypt(self, clear_message)
+    }
+
+    fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        AffineEngine::decrypt(self, cipher_message)
+    }
+}
+
+/// Inverse of `a` modulo `m`, computed with the extended Euclidean algorithm,
+/// if `a` and `m` are coprime
+fn modular_inverse(a: usize, m: usize) -> Option<usize> {
+    let (mut old_r, mut r) = (a as i64, m as i64);
+    let (mut old_s, mut s) = (1i64, 0i64);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+
+    // old_r is gcd(a, m), and old_s * a = gcd(a, m) mod m
+    (old_r == 1).then(|| old_s.rem_euclid(m as i64) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::IncompleteAscii;
+    use crate::{CaesarEngine, Shift};
+
+    #[test]
+    fn modular_inverses() {
+        assert_eq!(modular_inverse(5, 26), Some(21));
+        assert_eq!(modular_inverse(7, 60), Some(43));
+        assert_eq!(modular_inverse(1, 1), Some(0));
+        assert_eq!(modular_inverse(0, 26), None);
+        assert_eq!(modular_inverse(13, 26), None);
+        assert_eq!(modular_inverse(6, 60), None);
+    }
+
+    #[test]
+    fn multiplier_one_is_caesar() {
+        let message =
+            ClearText::<IncompleteAscii>::try_new("Ave Imperator, morituri te salutant").unwrap();
+        let affine_engine = AffineEngine::<IncompleteAscii>::try_new(1, 17).unwrap();
+        let caesar_engine = CaesarEngine::<IncompleteAscii>::new(Shift(17));
+
+        let encrypted_message = affine_engine.encrypt(&message);
+        assert_eq!(encrypted_message, caesar_engine.encrypt(&message));
+        assert_eq!(affine_engine.decrypt(&encrypted_message), message);
+    }
+
+    #[test]
+    fn keys_coprime_with_alphabet_len() {
+        let message =
+            ClearText::<IncompleteAscii>::try_new("Ave Imperator, morituri te salutant").unwrap();
+        for a in 0..60 {
+            match AffineEngine::<IncompleteAscii>::try_new(a, 11) {
+                Ok(engine) => {
+                    let (a_inverse, _) = engine.decryption_key();
+                    assert_eq!(a * a_inverse % 60, 1);
+                    let encrypted_message = engine.try_encrypt(&message).unwrap();
+                    assert_eq!(engine.try_decrypt(&encrypted_message).unwrap(), message);
+                }
+                Err(InvalidAffineKey::NotCoprime { .. }) => {
+                    assert!([2, 3, 5].iter().any(|p| a % p == 0))
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,23 @@
-use std::fmt::Debug;
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt::Debug;
 
 pub trait Alphabet: Debug {
     /// All the letters and symbols that composes the alphabet
     fn letters() -> &'static [char];
+
+    /// Name identifying the alphabet, e.g. when a text is serialized
+    ///
+    /// Defaults to the name of the type implementing the alphabet, as given by
+    /// [core::any::type_name], which may change between versions of the compiler.
+    /// Alphabets implemented by hand must override it for their serialized texts
+    /// and key files to be read by programs built with another compiler.
+    /// `#[derive(Alphabet)]` from the `caesar-cipher-macros` crate implements it.
+    fn name() -> &'static str {
+        core::any::type_name::<Self>()
+    }
 }
 
 /// This alphabet contains only lowercase ascii letters (and no symbols)
@@ -18,6 +33,10 @@ impl Alphabet for AsciiLowerCaseAlphabet {
 
         &CAESAR_ALPHABET
     }
+
+    fn name() -> &'static str {
+        "ascii-lowercase"
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -35,4 +54,351 @@ impl Alphabet for IncompleteAscii {
 
         &INCOMPLETE_ASCII
     }
+
+    fn name() -> &'static str {
+        "incomplete-ascii"
+    }
+}
+
+/// The lowercase Greek alphabet, in Unicode order
+///
+/// It includes the final sigma `'ς'` as a letter of its own, placed before `'σ'`,
+/// so that texts using it are decrypted back to the same form. Letters with
+/// diacritics (tonos, breathings, ...) are not part of the alphabet.
+#[derive(Debug, Eq, PartialEq)]
+pub struct GreekAlphabet;
+
+impl Alphabet for GreekAlphabet {
+    fn letters() -> &'static [char] {
+        const GREEK: [char; 25] = [
+            'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'λ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ',
+            'ς', 'σ', 'τ', 'υ', 'φ', 'χ', 'ψ', 'ω',
+        ];
+
+        &GREEK
+    }
+
+    fn name() -> &'static str {
+        "greek"
+    }
+}
+
+/// The 33 lowercase letters of the Russian Cyrillic alphabet, with `'ё'` after `'е'`
+#[derive(Debug, Eq, PartialEq)]
+pub struct RussianAlphabet;
+
+impl Alphabet for RussianAlphabet {
+    fn letters() -> &'static [char] {
+        const RUSSIAN: [char; 33] = [
+            'а', 'б', 'в', 'г', 'д', 'е', 'ё', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+            'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+        ];
+
+        &RUSSIAN
+    }
+
+    fn name() -> &'static str {
+        "russian"
+    }
+}
+
+/// The Hebrew alphabet, in Unicode order
+///
+/// The final forms (`'ך'`, `'ם'`, `'ן'`, `'ף'`, `'ץ'`) are letters of their own,
+/// each placed before its regular form, so that texts using them are decrypted
+/// back to the same form. Vowel points (niqqud) are not part of the alphabet.
+#[derive(Debug, Eq, PartialEq)]
+pub struct HebrewAlphabet;
+
+impl Alphabet for HebrewAlphabet {
+    fn letters() -> &'static [char] {
+        const HEBREW: [char; 27] = [
+            'א', 'ב', 'ג', 'ד', 'ה', 'ו', 'ז', 'ח', 'ט', 'י', 'ך', 'כ', 'ל', 'ם', 'מ', 'ן', 'נ',
+            'ס', 'ע', 'ף', 'פ', 'ץ', 'צ', 'ק', 'ר', 'ש', 'ת',
+        ];
+
+        &HEBREW
+    }
+
+    fn name() -> &'static str {
+        "hebrew"
+    }
+}
+
+/// The Arabic alphabet, in Unicode order
+///
+/// Besides the 28 letters, it includes the hamza and its seats (`'ء'`, `'آ'`, `'أ'`,
+/// `'ؤ'`, `'إ'`, `'ئ'`), the ta marbuta `'ة'` and the alif maqsura `'ى'`. Diacritics
+/// (harakat) and the tatweel are not part of the alphabet.
+///
+/// Letters are expected in their base form: text encoded with the contextual
+/// presentation forms can be converted with [normalize_arabic_presentation_forms].
+#[derive(Debug, Eq, PartialEq)]
+pub struct ArabicAlphabet;
+
+impl Alphabet for ArabicAlphabet {
+    fn letters() -> &'static [char] {
+        const ARABIC: [char; 36] = [
+            'ء', 'آ', 'أ', 'ؤ', 'إ', 'ئ', 'ا', 'ب', 'ة', 'ت', 'ث', 'ج', 'ح', 'خ', 'د', 'ذ', 'ر',
+            'ز', 'س', 'ش', 'ص', 'ض', 'ط', 'ظ', 'ع', 'غ', 'ف', 'ق', 'ك', 'ل', 'م', 'ن', 'ه', 'و',
+            'ى', 'ي',
+        ];
+
+        &ARABIC
+    }
+
+    fn name() -> &'static str {
+        "arabic"
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// First code point of the Arabic Presentation Forms-B letters
+const ARABIC_PRESENTATION_FORMS_START: u32 = 0xFE80;
+
+#[cfg(feature = "alloc")]
+/// Base form of the Arabic Presentation Forms-B letters, from `U+FE80`, with the
+/// number of consecutive presentation forms (isolated, final, initial, medial)
+/// of each letter or ligature.
+const ARABIC_PRESENTATION_FORMS: [(&str, u32); 40] = [
+    ("ء", 1),
+    ("آ", 2),
+    ("أ", 2),
+    ("ؤ", 2),
+    ("إ", 2),
+    ("ئ", 4),
+    ("ا", 2),
+    ("ب", 4),
+    ("ة", 2),
+    ("ت", 4),
+    ("ث", 4),
+    ("ج", 4),
+    ("ح", 4),
+    ("خ", 4),
+    ("د", 2),
+    ("ذ", 2),
+    ("ر", 2),
+    ("ز", 2),
+    ("س", 4),
+    ("ش", 4),
+    ("ص", 4),
+    ("ض", 4),
+    ("ط", 4),
+    ("ظ", 4),
+    ("ع", 4),
+    ("غ", 4),
+    ("ف", 4),
+    ("ق", 4),
+    ("ك", 4),
+    ("ل", 4),
+    ("م", 4),
+    ("ن", 4),
+    ("ه", 4),
+    ("و", 2),
+    ("ى", 2),
+    ("ي", 4),
+    ("لآ", 2),
+    ("لأ", 2),
+    ("لإ", 2),
+    ("لا", 2),
+];
+
+/// Replaces the Arabic presentation forms (isolated, initial, medial and final
+/// glyphs of the Arabic Presentation Forms-B block) by the base letters of
+/// [ArabicAlphabet], the lam-alef ligatures being split in two letters.
+///
+/// Other characters are left unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::normalize_arabic_presentation_forms;
+///
+/// // "سلام" written with its initial, medial, ligature and final glyphs
+/// assert_eq!(normalize_arabic_presentation_forms("\u{FEB3}\u{FEFC}\u{FEE2}"), "سلام");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn normalize_arabic_presentation_forms(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.chars() {
+        let mut offset = (c as u32).wrapping_sub(ARABIC_PRESENTATION_FORMS_START);
+        let base_form = ARABIC_PRESENTATION_FORMS
+            .iter()
+            .find_map(|&(base_form, len)| {
+                if offset < len {
+                    Some(base_form)
+                } else {
+                    offset -= len;
+                    None
+                }
+            });
+        match base_form {
+            Some(base_form) => normalized.push_str(base_form),
+            None => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum InvalidAlphabet {
+    /// The alphabet has no letters
+    Empty,
+    /// The letter appears more than once in the alphabet
+    DuplicateLetter(char),
+}
+
+/// Checks that the letters make a valid alphabet: it has at least one letter,
+/// and no letter appears twice. The letter reported as duplicate is the first one
+/// that appears again later.
+pub fn validate_letters(letters: &[char]) -> Result<(), InvalidAlphabet> {
+    if letters.is_empty() {
+        return Err(InvalidAlphabet::Empty);
+    }
+    match (1..letters.len()).find(|&i| letters[i..].contains(&letters[i - 1])) {
+        Some(i) => Err(InvalidAlphabet::DuplicateLetter(letters[i - 1])),
+        None => Ok(()),
+    }
+}
+
+/// Checks the letters of an [Alphabet] implemented by hand, see [validate_letters]
+///
+/// The engines assume that the alphabet is valid: with a duplicate letter, a
+/// message would not be decrypted back to itself. Alphabets implemented with
+/// `#[derive(Alphabet)]` from the `caesar-cipher-macros` crate are checked at
+/// compile time.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::{validate_alphabet, Alphabet, IncompleteAscii, InvalidAlphabet};
+///
+/// #[derive(Debug)]
+/// struct Binary;
+///
+/// impl Alphabet for Binary {
+///     fn letters() -> &'static [char] {
+///         &['0', '1', '0']
+///     }
+/// }
+///
+/// assert_eq!(validate_alphabet::<IncompleteAscii>(), Ok(()));
+/// assert_eq!(validate_alphabet::<Binary>(), Err(InvalidAlphabet::DuplicateLetter('0')));
+/// ```
+pub fn validate_alphabet<A: Alphabet>() -> Result<(), InvalidAlphabet> {
+    validate_letters(A::letters())
+}
+
+/// An alphabet defined at runtime, e.g. read from a configuration file
+///
+/// Unlike the [Alphabet] trait, which is implemented by types and so must be
+/// known at compile time, a `DynamicAlphabet` is a value. It is used with
+/// [DynamicCaesarEngine](crate::dynamic::DynamicCaesarEngine).
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::{DynamicAlphabet, InvalidAlphabet};
+///
+/// let alphabet = DynamicAlphabet::try_new("abcdef").unwrap();
+/// assert_eq!(alphabet.letters(), &['a', 'b', 'c', 'd', 'e', 'f']);
+///
+/// assert_eq!(DynamicAlphabet::try_new(""), Err(InvalidAlphabet::Empty));
+/// assert_eq!(
+///     DynamicAlphabet::try_new("abca"),
+///     Err(InvalidAlphabet::DuplicateLetter('a'))
+/// );
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DynamicAlphabet {
+    letters: Vec<char>,
+}
+
+#[cfg(feature = "alloc")]
+impl DynamicAlphabet {
+    /// Creates an alphabet made of the characters of `letters`, in order
+    pub fn try_new<T: ToString>(letters: T) -> Result<Self, InvalidAlphabet> {
+        Self::try_from_letters(letters.to_string().chars())
+    }
+
+    /// Creates an alphabet from an iterator of letters, in order
+    pub fn try_from_letters<I>(letters: I) -> Result<Self, InvalidAlphabet>
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let letters = letters.into_iter().collect::<Vec<_>>();
+        validate_letters(&letters)?;
+        Ok(Self { letters })
+    }
+
+    /// All the letters and symbols that composes the alphabet
+    pub fn letters(&self) -> &[char] {
+        &self.letters
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::{CaesarEngine, ClearText, ForeignCharPolicy, Shift};
+
+    fn round_trip<A: Alphabet>(sentence: &str) {
+        let message =
+            ClearText::<A>::try_new_with_policy(sentence, ForeignCharPolicy::Passthrough).unwrap();
+        for shift in 1..A::letters().len() {
+            let engine = CaesarEngine::<A>::new(Shift(shift as isize));
+            let encrypted_message = engine.encrypt(&message);
+            assert_ne!(encrypted_message.as_ref(), sentence);
+            assert_eq!(engine.decrypt(&encrypted_message).as_ref(), sentence);
+        }
+    }
+
+    #[test]
+    fn builtin_alphabets_are_valid() {
+        assert_eq!(validate_alphabet::<AsciiLowerCaseAlphabet>(), Ok(()));
+        assert_eq!(validate_alphabet::<IncompleteAscii>(), Ok(()));
+        assert_eq!(validate_alphabet::<GreekAlphabet>(), Ok(()));
+        assert_eq!(validate_alphabet::<RussianAlphabet>(), Ok(()));
+        assert_eq!(validate_alphabet::<HebrewAlphabet>(), Ok(()));
+        assert_eq!(validate_alphabet::<ArabicAlphabet>(), Ok(()));
+
+        assert_eq!(validate_letters(&[]), Err(InvalidAlphabet::Empty));
+        assert_eq!(
+            validate_letters(&['a', 'b', 'c', 'b', 'a']),
+            Err(InvalidAlphabet::DuplicateLetter('a'))
+        );
+        assert_eq!(
+            validate_letters(&['a', 'b', 'c', 'c']),
+            Err(InvalidAlphabet::DuplicateLetter('c'))
+        );
+    }
+
+    #[test]
+    fn unicode_alphabets_round_trip() {
+        round_trip::<GreekAlphabet>("ανερριφθω κυβος");
+        round_trip::<RussianAlphabet>("съешь же ещё этих мягких французских булок, да выпей чаю");
+        round_trip::<HebrewAlphabet>("דג סקרן שט בים מאוכזב ולפתע מצא חברה");
+        round_trip::<ArabicAlphabet>(
+            "نص حكيم له سر قاطع وذو شأن عظيم مكتوب على ثوب أخضر ومغلف بجلد أزرق",
+        );
+    }
+
+    #[test]
+    fn arabic_presentation_forms() {
+        // Isolated, final, initial and medial forms of beh
+        assert_eq!(
+            normalize_arabic_presentation_forms("\u{FE8F}\u{FE90}\u{FE91}\u{FE92}"),
+            "بببب"
+        );
+        assert_eq!(normalize_arabic_presentation_forms("\u{FE80}"), "ء");
+        assert_eq!(normalize_arabic_presentation_forms("\u{FEFB}"), "لا");
+        assert_eq!(normalize_arabic_presentation_forms("\u{FEF4} x"), "ي x");
+        for c in (0xFE80..=0xFEFC).filter_map(char::from_u32) {
+            let normalized = normalize_arabic_presentation_forms(&c.to_string());
+            assert!(normalized
+                .chars()
+                .all(|l| ArabicAlphabet::letters().contains(&l)));
+        }
+    }
 }
@@ -0,0 +1,379 @@
+//! Command line interface to encrypt, decrypt and crack messages
+//!
+//! ```text
+//! caesar encrypt --shift 3 [--alphabet NAME | --letters LETTERS] [--policy POLICY] [FILE]
+//! caesar decrypt --shift 3 [--alphabet NAME | --letters LETTERS] [--policy POLICY] [FILE]
+//! caesar crack [--top N] [--alphabet NAME | --letters LETTERS] [--policy POLICY] [FILE]
+//! caesar alphabets
+//! ```
+//!
+//! Messages are read from `FILE`, or from the standard input when it is
+//! missing or `-`, and each line is processed separately. Characters that are
+//! not in the alphabet are handled according to `POLICY`, which is `reject`
+//! (the default), `passthrough`, `drop`, or `replace=C` to replace them by `C`.
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use caesar_cipher::alphabets::{
+    Alphabet, ArabicAlphabet, AsciiLowerCaseAlphabet, DynamicAlphabet, GreekAlphabet,
+    HebrewAlphabet, IncompleteAscii, RussianAlphabet,
+};
+use caesar_cipher::crack::crack;
+use caesar_cipher::stream::{DecryptReader, EncryptWriter, StreamError};
+use caesar_cipher::{CaesarEngine, CharacterNotInAlphabet, CipherText, ForeignCharPolicy, Shift};
+
+const USAGE: &str = "\
+usage: caesar encrypt --shift N [--alphabet NAME | --letters LETTERS] [--policy POLICY] [FILE]
+       caesar decrypt --shift N [--alphabet NAME | --letters LETTERS] [--policy POLICY] [FILE]
+       caesar crack [--top N] [--alphabet NAME | --letters LETTERS] [--policy POLICY] [FILE]
+       caesar alphabets
+
+POLICY is reject (default), passthrough, drop or replace=C";
+
+/// Returns the letters of an alphabet, see [Alphabet::letters]
+type Letters = fn() -> &'static [char];
+
+/// Alphabets that can be selected by name with `--alphabet`
+const ALPHABETS: [(&str, Letters); 6] = [
+    ("ascii-lowercase", AsciiLowerCaseAlphabet::letters),
+    ("incomplete-ascii", IncompleteAscii::letters),
+    ("greek", GreekAlphabet::letters),
+    ("russian", RussianAlphabet::letters),
+    ("hebrew", HebrewAlphabet::letters),
+    ("arabic", ArabicAlphabet::letters),
+];
+
+const DEFAULT_ALPHABET: &str = "ascii-lowercase";
+
+/// Letters of the alphabet given on the command line
+static COMMAND_LINE_LETTERS: OnceLock<Vec<char>> = OnceLock::new();
+
+/// The alphabet given on the command line, so that the engines and the
+/// cryptanalysis of the library, which are written for a type implementing
+/// [Alphabet], can be used with an alphabet chosen at run time
+#[derive(Debug)]
+struct CommandLineAlphabet;
+
+impl CommandLineAlphabet {
+    /// Sets the letters of the alphabet, once before the first use of the alphabet
+    fn set(alphabet: &DynamicAlphabet) {
+        COMMAND_LINE_LETTERS
+            .set(alphabet.letters().to_vec())
+            .expect("the alphabet is only set once");
+    }
+}
+
+impl Alphabet for CommandLineAlphabet {
+    fn letters() -> &'static [char] {
+        COMMAND_LINE_LETTERS
+            .get()
+            .expect("the alphabet is set before being used")
+    }
+
+    fn name() -> &'static str {
+        "command-line"
+    }
+}
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    NotInAlphabet(CharacterNotInAlphabet),
+    /// Error while encrypting or decrypting a line of the input, counted from 1
+    Stream {
+        line: usize,
+        error: StreamError,
+    },
+    Io(io::Error),
+}
+
+impl CliError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Usage(_) => ExitCode::from(1),
+            Self::NotInAlphabet(_) | Self::Stream { .. } => ExitCode::from(2),
+            Self::Io(_) => ExitCode::from(3),
+        }
+    }
+
+    /// Error of the stream adapters while processing a line
+    fn from_line(line: usize, error: io::Error) -> Self {
+        let stream_error = error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<StreamError>())
+            .copied();
+        match stream_error {
+            Some(error) => Self::Stream { line, error },
+            None => Self::Io(error),
+        }
+    }
+}
+
+impl From<CharacterNotInAlphabet> for CliError {
+    fn from(error: CharacterNotInAlphabet) -> Self {
+        Self::NotInAlphabet(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Encrypt,
+    Decrypt,
+    Crack,
+    Alphabets,
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    shift: Option<isize>,
+    alphabet: DynamicAlphabet,
+    policy: ForeignCharPolicy,
+    top: usize,
+    input: Option<String>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, CliError> {
+    let command = match args.next().as_deref() {
+        Some("encrypt") => Command::Encrypt,
+        Some("decrypt") => Command::Decrypt,
+        Some("crack") => Command::Crack,
+        Some("alphabets") => Command::Alphabets,
+        Some(other) => return Err(CliError::Usage(format!("unknown command `{other}`"))),
+        None => return Err(CliError::Usage("missing command".to_string())),
+    };
+
+    let mut shift = None;
+    let mut alphabet = None;
+    let mut policy = ForeignCharPolicy::Reject;
+    let mut top = 3;
+    let mut input = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| CliError::Usage(format!("missing value for `{arg}`")))
+        };
+        match arg.as_str() {
+            "--shift" => shift = Some(parse_number(&value()?)?),
+            "--top" => top = parse_number(&value()?)?,
+            "--alphabet" => alphabet = Some(named_alphabet(&value()?)?),
+            "--policy" => policy = parse_policy(&value()?)?,
+            "--letters" => {
+                let letters = value()?;
+                let custom_alphabet = DynamicAlphabet::try_new(&letters).map_err(|error| {
+                    CliError::Usage(format!("invalid alphabet `{letters}`: {error:?}"))
+                })?;
+                alphabet = Some(custom_alphabet);
+            }
+            option if option.starts_with("--") => {
+                return Err(CliError::Usage(format!("unknown option `{option}`")))
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(CliError::Usage(format!("unexpected argument `{arg}`"))),
+        }
+    }
+
+    if matches!(command, Command::Encrypt | Command::Decrypt) && shift.is_none() {
+        return Err(CliError::Usage("missing `--shift`".to_string()));
+    }
+
+    Ok(Args {
+        command,
+        shift,
+        alphabet: match alphabet {
+            Some(alphabet) => alphabet,
+            None => named_alphabet(DEFAULT_ALPHABET)?,
+        },
+        policy,
+        top,
+        input: input.filter(|path| path != "-"),
+    })
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError::Usage(format!("invalid number `{value}`")))
+}
+
+fn parse_policy(value: &str) -> Result<ForeignCharPolicy, CliError> {
+    match value {
+        "reject" => Ok(ForeignCharPolicy::Reject),
+        "passthrough" => Ok(ForeignCharPolicy::Passthrough),
+        "drop" => Ok(ForeignCharPolicy::Drop),
+        _ => {
+            let mut placeholder = value.strip_prefix("replace=").unwrap_or_default().chars();
+            match (placeholder.next(), placeholder.next()) {
+                (Some(placeholder), None) => Ok(ForeignCharPolicy::Replace(placeholder)),
+                _ => Err(CliError::Usage(format!("invalid policy `{value}`"))),
+            }
+        }
+    }
+}
+
+fn named_alphabet(name: &str) -> Result<DynamicAlphabet, CliError> {
+    let (_, letters) = ALPHABETS
+        .iter()
+        .find(|(alphabet_name, _)| *alphabet_name == name)
+        .ok_or_else(|| CliError::Usage(format!("unknown alphabet `{name}`")))?;
+    DynamicAlphabet::try_from_letters(letters().iter().copied())
+        .map_err(|error| CliError::Usage(format!("invalid alphabet `{name}`: {error:?}")))
+}
+
+fn open_input(input: Option<&str>) -> Result<Box<dyn BufRead>, CliError> {
+    let reader: Box<dyn Read> = match input {
+        Some(path) => Box::new(std::fs::File::open(path)?),
+        None => Box::new(io::stdin()),
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+/// Reads the lines of the input one at a time, without their line ending
+fn for_each_line<F>(input: Box<dyn BufRead>, mut process_line: F) -> Result<(), CliError>
+where
+    F: FnMut(usize, &[u8]) -> Result<(), CliError>,
+{
+    for (index, line) in input.split(b'\n').enumerate() {
+        let line = line?;
+        process_line(index + 1, line.strip_suffix(b"\r").unwrap_or(&line))?;
+    }
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), CliError> {
+    let mut stdout = io::stdout().lock();
+    if args.command == Command::Alphabets {
+        for (name, letters) in ALPHABETS {
+            writeln!(stdout, "{name}\t{}", letters().iter().collect::<String>())?;
+        }
+        return Ok(());
+    }
+
+    CommandLineAlphabet::set(&args.alphabet);
+    let input = open_input(args.input.as_deref())?;
+    match args.command {
+        Command::Alphabets => unreachable!("handled above"),
+        Command::Encrypt => {
+            let shift = Shift(args.shift.expect("checked when parsing arguments"));
+            let engine = CaesarEngine::<CommandLineAlphabet>::new(shift);
+            for_each_line(input, |line_number, line| {
+                let mut writer = EncryptWriter::new(&engine, &mut stdout).with_policy(args.policy);
+                writer
+                    .write_all(line)
+                    .and_then(|()| writer.finish().map(drop))
+                    .map_err(|error| CliError::from_line(line_number, error))?;
+                Ok(stdout.write_all(b"\n")?)
+            })?;
+        }
+        Command::Decrypt => {
+            let shift = Shift(args.shift.expect("checked when parsing arguments"));
+            let engine = CaesarEngine::<CommandLineAlphabet>::new(shift);
+            for_each_line(input, |line_number, line| {
+                let mut reader = DecryptReader::new(&engine, line).with_policy(args.policy);
+                io::copy(&mut reader, &mut stdout)
+                    .map_err(|error| CliError::from_line(line_number, error))?;
+                Ok(stdout.write_all(b"\n")?)
+            })?;
+        }
+        Command::Crack => {
+            // The whole message is needed to score its decryptions
+            let mut lines = Vec::new();
+            for line in input.lines() {
+                let line =
+                    CipherText::<CommandLineAlphabet>::try_new_with_policy(line?, args.policy)?;
+                lines.push(line.as_ref().clone());
+            }
+            let message: CipherText<CommandLineAlphabet> =
+                CipherText::try_new_with_policy(lines.join("\n"), ForeignCharPolicy::Passthrough)?;
+
+            for candidate in crack(&message).into_iter().take(args.top) {
+                writeln!(
+                    stdout,
+                    "# shift {} score {:.2}",
+                    candidate.shift.0, candidate.score
+                )?;
+                writeln!(stdout, "{}", candidate.clear_text.as_ref())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = parse_args(std::env::args().skip(1)).and_then(run);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            match &error {
+                CliError::Usage(message) => eprintln!("error: {message}\n\n{USAGE}"),
+                CliError::NotInAlphabet(not_in_alphabet) => eprintln!("error: {not_in_alphabet}"),
+                CliError::Stream { line, error } => eprintln!("error: line {line}: {error}"),
+                CliError::Io(io_error) => eprintln!("error: {io_error}"),
+            }
+            error.exit_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, CliError> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_command_line() {
+        let args = parse(&[
+            "encrypt",
+            "--shift",
+            "3",
+            "--alphabet",
+            "incomplete-ascii",
+            "-",
+        ])
+        .unwrap();
+        assert_eq!(args.command, Command::Encrypt);
+        assert_eq!(args.shift, Some(3));
+        assert_eq!(args.alphabet.letters(), IncompleteAscii::letters());
+        assert_eq!(args.input, None);
+
+        assert_eq!(args.policy, ForeignCharPolicy::Reject);
+
+        let args = parse(&["decrypt", "--shift", "-3", "--letters", "abc"]).unwrap();
+        assert_eq!(args.shift, Some(-3));
+
+        let args = parse(&["encrypt", "--shift", "3", "--policy", "passthrough"]).unwrap();
+        assert_eq!(args.policy, ForeignCharPolicy::Passthrough);
+        let args = parse(&["encrypt", "--shift", "3", "--policy", "replace=_"]).unwrap();
+        assert_eq!(args.policy, ForeignCharPolicy::Replace('_'));
+        assert!(matches!(
+            parse(&["encrypt", "--shift", "3", "--policy", "replace=ab"]),
+            Err(CliError::Usage(_))
+        ));
+
+        let args = parse(&["crack", "--letters", "abc", "message.txt"]).unwrap();
+        assert_eq!(args.command, Command::Crack);
+        assert_eq!(args.alphabet.letters(), &['a', 'b', 'c']);
+        assert_eq!(args.input.as_deref(), Some("message.txt"));
+
+        assert!(matches!(parse(&["decrypt"]), Err(CliError::Usage(_))));
+        assert!(matches!(
+            parse(&["crack", "--letters", "aa"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(
+            parse(&["crack", "--alphabet", "klingon"]),
+            Err(CliError::Usage(_))
+        ));
+    }
+}
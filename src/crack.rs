@@ -0,0 +1,267 @@
+//! Recovery of Caesar encrypted messages without the key
+//!
+//! There are only as many Caesar keys as there are letters in the alphabet, so
+//! a message can be decrypted by trying every [Shift] and keeping the candidate
+//! plaintexts that look the most like the expected language.
+//!
+//! Messages too short for their letters to look like a language can still be
+//! decrypted when a part of the plaintext is known, see [crib_shifts].
+use alloc::vec::Vec;
+
+use crate::alphabets::Alphabet;
+use crate::language::LanguageModel;
+use crate::table::CharTable;
+use crate::{CaesarEngine, CharacterNotInAlphabet, CipherText, ClearText, Shift};
+
+/// Frequencies of the letters `'a'` to `'z'` in English texts
+const ENGLISH_LETTER_FREQUENCIES: [f64; 26] = [
+    0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015, 0.06094, 0.06966, 0.00153,
+    0.00772, 0.04025, 0.02406, 0.06749, 0.07507, 0.01929, 0.00095, 0.05987, 0.06327, 0.09056,
+    0.02758, 0.00978, 0.02360, 0.00150, 0.01974, 0.00074,
+];
+
+/// A possible decryption of a message
+#[derive(Debug)]
+pub struct Candidate<A> {
+    /// The shift the message would have been encrypted with
+    pub shift: Shift,
+    /// How far the plaintext is from the expected language, lower is better
+    pub score: f64,
+    pub clear_text: ClearText<A>,
+}
+
+/// Tries every shift of the alphabet, and ranks the decrypted messages by how
+/// close they are to English, the most likely candidate first.
+///
+/// Candidates are scored with [english_chi_squared], see [crack_with_model]
+/// for other languages.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::IncompleteAscii;
+/// use caesar_cipher::crack::crack;
+/// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+///
+/// let message = ClearText::<IncompleteAscii>::try_new(
+///     "The die is cast. Ave Imperator, morituri te salutant."
+/// ).unwrap();
+/// let encrypted_message = CaesarEngine::<IncompleteAscii>::new(Shift(17)).encrypt(&message);
+///
+/// let candidates = crack(&encrypted_message);
+/// assert_eq!(candidates.len(), 60);
+/// assert_eq!(candidates[0].shift, Shift(17));
+/// assert_eq!(candidates[0].clear_text, message);
+/// ```
+pub fn crack<A: Alphabet>(cipher_message: &CipherText<A>) -> Vec<Candidate<A>> {
+    crack_with_model(cipher_message, &english_chi_squared)
+}
+
+/// Tries every shift of the alphabet, and ranks the decrypted messages by their
+/// score with the language model, the most likely candidate first.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::crack::crack_with_model;
+/// use caesar_cipher::language::Language;
+/// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("aleaiactaest").unwrap();
+/// let encrypted_message = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3)).encrypt(&message);
+///
+/// let candidates = crack_with_model(&encrypted_message, &Language::Latin.dictionary());
+/// assert_eq!(candidates[0].shift, Shift(3));
+/// ```
+pub fn crack_with_model<A: Alphabet, M: LanguageModel + ?Sized>(
+    cipher_message: &CipherText<A>,
+    model: &M,
+) -> Vec<Candidate<A>> {
+    let mut candidates = (0..A::letters().len() as isize)
+        .map(|shift| {
+            let clear_text = CaesarEngine::<A>::new(Shift(shift)).decrypt(cipher_message);
+            Candidate {
+                shift: Shift(shift),
+                score: model.score(&clear_text.message),
+                clear_text,
+            }
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+    candidates
+}
+
+/// A shift that decrypts part of the message into the crib
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CribMatch {
+    /// Index, in characters, of the start of the crib in the message
+    pub offset: usize,
+    /// The shift the message would have been encrypted with, in `0..A::letters().len()`
+    pub shift: Shift,
+}
+
+/// Finds the shifts consistent with a known part of the plaintext, the crib,
+/// at every position of the message
+///
+/// The crib is consistent at a position if shifting each of its letters by the
+/// same number of positions in the alphabet gives the characters of the message
+/// at this position. Matches are ordered by offset. An empty crib matches nothing.
+///
+/// # Errors
+///
+/// Returns the first character of the crib that is not in the alphabet.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::IncompleteAscii;
+/// use caesar_cipher::crack::{crib_shifts, CribMatch};
+/// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+///
+/// let message = ClearText::<IncompleteAscii>::try_new("Ave, Caesar").unwrap();
+/// let encrypted_message = CaesarEngine::<IncompleteAscii>::new(Shift(-5)).encrypt(&message);
+///
+/// let matches = crib_shifts(&encrypted_message, "Ave").unwrap();
+/// assert_eq!(matches, [CribMatch { offset: 0, shift: Shift(55) }]);
+/// assert_eq!(
+///     CaesarEngine::<IncompleteAscii>::new(matches[0].shift).decrypt(&encrypted_message),
+///     message
+/// );
+/// ```
+pub fn crib_shifts<A: Alphabet>(
+    cipher_message: &CipherText<A>,
+    crib: &str,
+) -> Result<Vec<CribMatch>, CharacterNotInAlphabet> {
+    let alphabet_len = A::letters().len();
+    let letter_indices = CharTable::new(A::letters().iter().copied().zip(0..));
+    let crib = crib
+        .char_indices()
+        .enumerate()
+        .map(|(index, (byte_offset, c))| {
+            letter_indices
+                .get(c)
+                .ok_or(CharacterNotInAlphabet::new(c, index, byte_offset))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    // Characters that are not in the alphabet were left as they are, so they never match
+    let cipher = cipher_message
+        .cipher
+        .chars()
+        .map(|c| letter_indices.get(c))
+        .collect::<Vec<_>>();
+    if crib.is_empty() || crib.len() > cipher.len() {
+        return Ok(Vec::new());
+    }
+
+    let matches = cipher
+        .windows(crib.len())
+        .enumerate()
+        .filter_map(|(offset, window)| {
+            let shift = (window[0]? + alphabet_len - crib[0]) % alphabet_len;
+            window
+                .iter()
+                .zip(&crib)
+                .all(|(&cipher, &clear)| cipher == Some((clear + shift) % alphabet_len))
+                .then_some(CribMatch {
+                    offset,
+                    shift: Shift(shift as isize),
+                })
+        })
+        .collect();
+    Ok(matches)
+}
+
+/// [Chi-squared statistic](https://en.wikipedia.org/wiki/Chi-squared_test) of the
+/// letters of the text against the frequencies of letters in English.
+///
+/// Letters are counted regardless of their case. Every other character that is
+/// not a whitespace is counted as a letter that never occurs in English, so that
+/// texts with a lot of symbols get a worse (higher) score.
+pub fn english_chi_squared(text: &str) -> f64 {
+    let mut letter_counts = [0usize; 26];
+    let mut total = 0usize;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        total += 1;
+        if c.is_ascii_alphabetic() {
+            letter_counts[(c.to_ascii_lowercase() as u8 - b'a') as usize] += 1;
+        }
+    }
+    if total == 0 {
+        return f64::INFINITY;
+    }
+
+    letter_counts
+        .iter()
+        .zip(ENGLISH_LETTER_FREQUENCIES)
+        .map(|(&observed, frequency)| {
+            let expected = frequency * total as f64;
+            let difference = observed as f64 - expected;
+            difference * difference / expected
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::AsciiLowerCaseAlphabet;
+
+    #[test]
+    fn crack_lowercase_message() {
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new(
+            "concreteisbasedonthelearningwitherrorsproblemwhichiswellstudied",
+        )
+        .unwrap();
+        for shift in [0, 1, 13, 25] {
+            let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(shift));
+            let candidates = crack(&engine.encrypt(&message));
+
+            assert_eq!(candidates.len(), 26);
+            assert_eq!(candidates[0].shift, Shift(shift));
+            assert_eq!(candidates[0].clear_text, message);
+            assert!(candidates.windows(2).all(|c| c[0].score <= c[1].score));
+        }
+    }
+
+    #[test]
+    fn crib_at_every_offset() {
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("meetmeatnoon").unwrap();
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(-30));
+        let encrypted_message = engine.encrypt(&message);
+
+        assert_eq!(
+            crib_shifts(&encrypted_message, "ee").unwrap(),
+            [
+                CribMatch {
+                    offset: 1,
+                    shift: Shift(22)
+                },
+                // "oo" is also two times the same letter
+                CribMatch {
+                    offset: 9,
+                    shift: Shift(6)
+                },
+            ]
+        );
+        assert_eq!(
+            crib_shifts(&encrypted_message, "noon").unwrap(),
+            [CribMatch {
+                offset: 8,
+                shift: Shift(22)
+            }]
+        );
+        assert_eq!(crib_shifts(&encrypted_message, "dusk").unwrap(), []);
+        assert_eq!(crib_shifts(&encrypted_message, "").unwrap(), []);
+        assert_eq!(
+            crib_shifts(&encrypted_message, "at noon"),
+            Err(CharacterNotInAlphabet::new(' ', 2, 2))
+        );
+    }
+
+    #[test]
+    fn chi_squared_prefers_english() {
+        assert!(english_chi_squared("hello world") < english_chi_squared("khoor zruog"));
+        assert_eq!(english_chi_squared(" "), f64::INFINITY);
+    }
+}
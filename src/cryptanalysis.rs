@@ -0,0 +1,578 @@
+//! Recovery of Vigenère and substitution encrypted messages without the key
+//!
+//! # Vigenère
+//!
+//! The length of the keyword is estimated first, with two statistics:
+//!
+//! - the [Kasiski examination](https://en.wikipedia.org/wiki/Kasiski_examination):
+//!   sequences of letters repeated in the cipher text are often the same plaintext
+//!   encrypted with the same part of the keyword, so the distance between them is a
+//!   multiple of the keyword length.
+//! - the [index of coincidence](https://en.wikipedia.org/wiki/Index_of_coincidence):
+//!   with the right length, each column of letters encrypted by the same letter of the
+//!   keyword is a Caesar encrypted text, which keeps the uneven letter frequencies
+//!   of the language, while mixing several shifts flattens them.
+//!
+//! Each column is then cracked as a Caesar cipher, with the same scoring as
+//! [crack](crate::crack::crack).
+//!
+//! # Substitution
+//!
+//! A monoalphabetic substitution has too many keys to try them all, and letter
+//! frequencies only give the most frequent letters. The [SubstitutionSolver]
+//! starts from the key matching the letters by frequency, and improves it by
+//! [hill climbing](https://en.wikipedia.org/wiki/Hill_climbing): two letters of
+//! the key are swapped at random, and the swap is kept when the decrypted message
+//! gets a better score from a [LanguageModel], usually quadgrams.
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::alphabets::Alphabet;
+use crate::crack::english_chi_squared;
+use crate::language::{Language, LanguageModel, NGramModel, UnigramModel};
+use crate::rng::SplitMix64;
+use crate::table::CharTable;
+use crate::vigenere::VigenereEngine;
+use crate::{CipherText, ClearText};
+
+/// Length of the repeated sequences looked for by the Kasiski examination
+const KASISKI_SEQUENCE_LEN: usize = 3;
+
+/// Number of random swaps of the best key a restart of the substitution solver starts from
+const PERTURBATION_SWAPS: usize = 4;
+
+/// A possible length of the keyword
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyLength {
+    pub length: usize,
+    /// Average index of coincidence of the columns of letters encrypted
+    /// by the same letter of the keyword
+    pub index_of_coincidence: f64,
+    /// Proportion of the distances between repeated sequences that
+    /// are multiples of the length
+    pub kasiski_ratio: f64,
+    /// Likelihood of the length, between 0 and 1, the confidences
+    /// of all the estimated lengths sum to 1
+    pub confidence: f64,
+}
+
+/// A possible decryption of a message
+#[derive(Debug)]
+pub struct KeyCandidate<A> {
+    pub keyword: String,
+    /// Likelihood of the keyword, between 0 and 1, taken from its [KeyLength]
+    pub confidence: f64,
+    /// How far the plaintext is from English, lower is better
+    pub score: f64,
+    pub clear_text: ClearText<A>,
+}
+
+/// Estimates the length of the keyword the message was encrypted with, trying every
+/// length up to `max_length`, the most likely length first.
+///
+/// Characters that are not in the alphabet are ignored, as they do not consume
+/// a letter of the keyword.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::cryptanalysis::estimate_key_length;
+/// use caesar_cipher::vigenere::VigenereEngine;
+/// use caesar_cipher::ClearText;
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new(
+///     "itwasthebestoftimesitwastheworstoftimesitwastheageofwisdomitwastheageoffoolishness\
+///      itwastheepochofbeliefitwastheepochofincredulityitwastheseasonoflight\
+///      itwastheseasonofdarknessitwasthespringofhopeitwasthewinterofdespair",
+/// )
+/// .unwrap();
+/// let engine = VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("lemon").unwrap();
+///
+/// let key_lengths = estimate_key_length(&engine.encrypt(&message), 10);
+/// assert_eq!(key_lengths[0].length, 5);
+/// ```
+pub fn estimate_key_length<A: Alphabet>(
+    cipher_message: &CipherText<A>,
+    max_length: usize,
+) -> Vec<KeyLength> {
+    let alphabet_len = A::letters().len();
+    let indices = letter_indices::<A>(&cipher_message.cipher);
+    let distances = repeated_sequence_distances(&indices);
+
+    // Each column needs at least two letters for its index of coincidence
+    let max_length = max_length.min(indices.len() / 2);
+    let mut key_lengths = Vec::with_capacity(max_length);
+    let mut scores = Vec::with_capacity(max_length);
+    for length in 1..=max_length {
+        let index_of_coincidence = (0..length)
+            .map(|column| {
+                let column = indices.iter().skip(column).step_by(length);
+                index_of_coincidence(column, alphabet_len)
+            })
+            .sum::<f64>()
+            / length as f64;
+        let kasiski_ratio = if distances.is_empty() {
+            0.0
+        } else {
+            let multiples = distances
+                .iter()
+                .filter(|d| d.is_multiple_of(length))
+                .count();
+            multiples as f64 / distances.len() as f64
+        };
+
+        // A random text has an index of coincidence of about `1 / alphabet_len`,
+        // the length is scored by how far above random its columns are. Multiples
+        // of the right length have as good columns, but fewer repeated sequences.
+        let above_random = (index_of_coincidence * alphabet_len as f64 - 1.0).max(0.0);
+        scores.push(above_random * (0.5 + kasiski_ratio));
+        key_lengths.push(KeyLength {
+            length,
+            index_of_coincidence,
+            kasiski_ratio,
+            confidence: 0.0,
+        });
+    }
+
+    let total_score = scores.iter().sum::<f64>();
+    for (key_length, score) in key_lengths.iter_mut().zip(&scores) {
+        key_length.confidence = if total_score > 0.0 {
+            score / total_score
+        } else {
+            1.0 / max_length as f64
+        };
+    }
+    key_lengths.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    key_lengths
+}
+
+/// Cracks the message for every length estimated by [estimate_key_length],
+/// the most likely keyword first.
+///
+/// Each column of the message is cracked as a Caesar cipher, keeping the shift
+/// whose decryption is the closest to English. A keyword that repeats a shorter
+/// one, found for a multiple of the right length, is merged with the shorter one.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::cryptanalysis::crack_vigenere;
+/// use caesar_cipher::vigenere::VigenereEngine;
+/// use caesar_cipher::ClearText;
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new(
+///     "itwasthebestoftimesitwastheworstoftimesitwastheageofwisdomitwastheageoffoolishness\
+///      itwastheepochofbeliefitwastheepochofincredulityitwastheseasonoflight\
+///      itwastheseasonofdarknessitwasthespringofhopeitwasthewinterofdespair",
+/// )
+/// .unwrap();
+/// let engine = VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("dickens").unwrap();
+///
+/// let candidates = crack_vigenere(&engine.encrypt(&message), 12);
+/// assert_eq!(candidates[0].keyword, "dickens");
+/// assert_eq!(candidates[0].clear_text, message);
+/// ```
+pub fn crack_vigenere<A: Alphabet>(
+    cipher_message: &CipherText<A>,
+    max_key_length: usize,
+) -> Vec<KeyCandidate<A>> {
+    let alphabet = A::letters();
+    let indices = letter_indices::<A>(&cipher_message.cipher);
+
+    let mut candidates: Vec<KeyCandidate<A>> = Vec::new();
+    for key_length in estimate_key_length(cipher_message, max_key_length) {
+        let keyword = (0..key_length.length)
+            .map(|column| {
+                let column = indices
+                    .iter()
+                    .skip(column)
+                    .step_by(key_length.length)
+                    .copied()
+                    .collect::<Vec<_>>();
+                alphabet[crack_column(&column, alphabet)]
+            })
+            .collect::<Vec<_>>();
+        let keyword = shortest_period(&keyword).iter().collect::<String>();
+
+        match candidates.iter_mut().find(|c| c.keyword == keyword) {
+            Some(candidate) => candidate.confidence += key_length.confidence,
+            None => {
+                let engine =
+                    VigenereEngine::<A>::try_new(&keyword).expect("keyword letters are in A");
+                let clear_text = engine.decrypt(cipher_message);
+                candidates.push(KeyCandidate {
+                    confidence: key_length.confidence,
+                    score: english_chi_squared(&clear_text.message),
+                    keyword,
+                    clear_text,
+                });
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    candidates
+}
+
+/// A possible decryption of a substitution encrypted message
+#[derive(Debug)]
+pub struct SubstitutionCandidate<A> {
+    /// The replacement of each letter of the alphabet, as in
+    /// [SubstitutionEngine::key](crate::substitution::SubstitutionEngine::key)
+    pub key: Vec<char>,
+    /// The score of the plaintext with the language model, lower is better
+    pub score: f64,
+    pub clear_text: ClearText<A>,
+}
+
+/// Hill climbing solver for messages encrypted with a monoalphabetic substitution
+///
+/// The first climb starts from the key that replaces the letters by frequency
+/// order, the most frequent letter of the language by the most frequent letter of
+/// the message. The following climbs, the restarts, start from the best key found
+/// so far with a few letters swapped at random, to get out of a local optimum. Each
+/// climb tries a number of swaps of two letters of the key, its iterations. The
+/// random swaps are drawn from a seed, so the same seed always gives the same result.
+///
+/// Letters that are rare in the message are often left in the wrong place, and the
+/// models read texts regardless of case, so the solver is better suited to messages
+/// of a few hundred letters, in alphabets without uppercase letters.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::cryptanalysis::SubstitutionSolver;
+/// use caesar_cipher::language::Language;
+/// use caesar_cipher::substitution::SubstitutionEngine;
+/// use caesar_cipher::ClearText;
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new(
+///     "thecommitteemetontuesdaymorningtodiscussthebudgetforthecomingyear\
+///      andafteralongdebatethemembersagreedthattheschoolwouldreceivemoremoney\
+///      forbooksandteacherswhiletheroadsandtheoldbridgeneartheriverwouldhave\
+///      towaituntilthespring",
+/// )
+/// .unwrap();
+/// let engine = SubstitutionEngine::<AsciiLowerCaseAlphabet>::random(42);
+///
+/// let solver = SubstitutionSolver::for_language(Language::English).with_seed(7);
+/// let candidate = solver.solve(&engine.encrypt(&message));
+/// assert_eq!(candidate.clear_text, message);
+/// # }
+/// ```
+pub struct SubstitutionSolver<M> {
+    letters: UnigramModel,
+    model: M,
+    seed: u64,
+    restarts: usize,
+    iterations: usize,
+}
+
+impl SubstitutionSolver<(NGramModel, NGramModel)> {
+    /// Creates a solver with the letter frequencies of the language, scoring
+    /// the decrypted messages with both its quadgrams and its bigrams
+    ///
+    /// Most of the quadgrams of a wrong decryption are missing from the small
+    /// embedded tables, and get the same score. Bigrams still tell which of
+    /// these decryptions are closer to the language.
+    pub fn for_language(language: Language) -> Self {
+        Self::new(
+            language.letters(),
+            (language.quadgrams(), language.bigrams()),
+        )
+    }
+}
+
+impl<M: LanguageModel> SubstitutionSolver<M> {
+    /// Creates a solver ordering the letters with `letters`, and scoring the
+    /// decrypted messages with `model`
+    ///
+    /// By default, the seed is 0, and there are 5 restarts of 2000 iterations.
+    pub fn new(letters: UnigramModel, model: M) -> Self {
+        Self {
+            letters,
+            model,
+            seed: 0,
+            restarts: 5,
+            iterations: 2000,
+        }
+    }
+
+    /// Sets the seed of the random swaps
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the number of climbs after the first one, each starting from the best
+    /// key found so far with a few letters swapped at random
+    pub fn with_restarts(mut self, restarts: usize) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    /// Sets the number of swaps tried by each climb
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Finds the key whose decryption of the message has the best score
+    pub fn solve<A: Alphabet>(&self, cipher_message: &CipherText<A>) -> SubstitutionCandidate<A> {
+        let alphabet = A::letters();
+        let letter_indices = CharTable::new(alphabet.iter().copied().zip(0..));
+        let cipher = cipher_message
+            .cipher
+            .chars()
+            .map(|c| letter_indices.get(c).ok_or(c))
+            .collect::<Vec<_>>();
+
+        // The decryption key maps each letter of the message to a letter of the plaintext
+        let mut counts = vec![0usize; alphabet.len()];
+        for &index in cipher.iter().flatten() {
+            counts[index] += 1;
+        }
+        let mut cipher_order = (0..alphabet.len()).collect::<Vec<_>>();
+        cipher_order.sort_by_key(|&index| core::cmp::Reverse(counts[index]));
+        let mut clear_order = (0..alphabet.len()).collect::<Vec<_>>();
+        clear_order.sort_by(|&a, &b| {
+            let frequency = |index: usize| self.letters.frequency(alphabet[index]);
+            frequency(b).total_cmp(&frequency(a))
+        });
+        let mut frequency_key = vec![0; alphabet.len()];
+        for (&cipher_index, &clear_index) in cipher_order.iter().zip(&clear_order) {
+            frequency_key[cipher_index] = clear_index;
+        }
+
+        let mut rng = SplitMix64::new(self.seed);
+        let mut decrypted = String::with_capacity(cipher_message.cipher.len());
+        let mut score = |key: &[usize]| {
+            decrypted.clear();
+            decrypted.extend(
+                cipher
+                    .iter()
+                    .map(|&c| c.map_or_else(|c| c, |i| alphabet[key[i]])),
+            );
+            self.model.score(&decrypted)
+        };
+
+        let mut best: Option<(Vec<usize>, f64)> = None;
+        for restart in 0..=self.restarts {
+            let mut key = match &best {
+                Some((best_key, _)) if restart > 0 => best_key.clone(),
+                _ => frequency_key.clone(),
+            };
+            if restart > 0 {
+                for _ in 0..PERTURBATION_SWAPS {
+                    key.swap(rng.below(alphabet.len()), rng.below(alphabet.len()));
+                }
+            }
+            let mut key_score = score(&key);
+            for _ in 0..self.iterations {
+                let (a, b) = (rng.below(alphabet.len()), rng.below(alphabet.len()));
+                key.swap(a, b);
+                let swapped_score = score(&key);
+                if swapped_score < key_score {
+                    key_score = swapped_score;
+                } else {
+                    key.swap(a, b);
+                }
+            }
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_score)| key_score < *best_score)
+            {
+                best = Some((key, key_score));
+            }
+        }
+
+        let (decryption_key, score) = best.expect("there is at least one climb");
+        let mut key = alphabet.to_vec();
+        for (cipher_index, &clear_index) in decryption_key.iter().enumerate() {
+            key[clear_index] = alphabet[cipher_index];
+        }
+        let clear_text = ClearText {
+            _marker: Default::default(),
+            message: cipher
+                .iter()
+                .map(|&c| c.map_or_else(|c| c, |i| alphabet[decryption_key[i]]))
+                .collect(),
+        };
+        SubstitutionCandidate {
+            key,
+            score,
+            clear_text,
+        }
+    }
+}
+
+/// Positions in the alphabet of the letters of the text,
+/// skipping the characters that are not in the alphabet
+fn letter_indices<A: Alphabet>(text: &str) -> Vec<usize> {
+    let letter_indices = CharTable::new(A::letters().iter().copied().zip(0..));
+    text.chars().filter_map(|c| letter_indices.get(c)).collect()
+}
+
+/// Probability that two letters taken at random in the text are the same
+fn index_of_coincidence<'a, I>(indices: I, alphabet_len: usize) -> f64
+where
+    I: IntoIterator<Item = &'a usize>,
+{
+    let mut counts = vec![0usize; alphabet_len];
+    let mut total = 0;
+    for &index in indices {
+        counts[index] += 1;
+        total += 1;
+    }
+    if total < 2 {
+        return 0.0;
+    }
+    let pairs = counts
+        .iter()
+        .map(|&n| n * n.saturating_sub(1))
+        .sum::<usize>();
+    pairs as f64 / (total * (total - 1)) as f64
+}
+
+/// Distances between consecutive occurrences of the same sequence of letters
+fn repeated_sequence_distances(indices: &[usize]) -> Vec<usize> {
+    let mut last_positions = BTreeMap::new();
+    let mut distances = Vec::new();
+    for (position, sequence) in indices.windows(KASISKI_SEQUENCE_LEN).enumerate() {
+        if let Some(last_position) = last_positions.insert(sequence, position) {
+            distances.push(position - last_position);
+        }
+    }
+    distances
+}
+
+/// The shift of the column of letters whose decryption is the closest to English
+fn crack_column(column: &[usize], alphabet: &[char]) -> usize {
+    let mut decrypted = String::with_capacity(column.len());
+    (0..alphabet.len())
+        .map(|shift| {
+            decrypted.clear();
+            decrypted.extend(
+                column
+                    .iter()
+                    .map(|&index| alphabet[(index + alphabet.len() - shift) % alphabet.len()]),
+            );
+            (shift, english_chi_squared(&decrypted))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(shift, _)| shift)
+        .expect("alphabets are not empty")
+}
+
+/// The shortest prefix of the keyword that gives the whole keyword when repeated
+fn shortest_period(keyword: &[char]) -> &[char] {
+    let period = (1..keyword.len())
+        .filter(|&period| keyword.len().is_multiple_of(period))
+        .find(|&period| {
+            keyword
+                .chunks(period)
+                .all(|chunk| chunk == &keyword[..period])
+        })
+        .unwrap_or(keyword.len());
+    &keyword[..period]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+    use crate::vigenere::VigenereEngine;
+
+    /// Beginning of Caesar's Commentaries on the Gallic War, in English
+    pub(crate) const GALLIC_WAR: &str = "All Gaul is divided into three parts, one of which \
+        the Belgae inhabit, the Aquitani another, those who in their own language are called \
+        Celts, in our Gauls, the third. All these differ from each other in language, customs \
+        and laws. The river Garonne separates the Gauls from the Aquitani, the Marne and the \
+        Seine separate them from the Belgae. Of all these, the Belgae are the bravest, because \
+        they are furthest from the civilization and refinement of our Province, and merchants \
+        least frequently resort to them, and import those things which tend to effeminate the \
+        mind, and they are the nearest to the Germans, who dwell beyond the Rhine, with whom \
+        they are continually waging war. For which reason the Helvetii also surpass the rest \
+        of the Gauls in valor, as they contend with the Germans in almost daily battles, when \
+        they either repel them from their own territories, or themselves wage war on their \
+        frontiers.";
+
+    #[test]
+    fn crack_mixed_case_message() {
+        let message = ClearText::<IncompleteAscii>::try_new(GALLIC_WAR).unwrap();
+        for keyword in ["Caesar", "Vercingetorix", "Alesia"] {
+            let engine = VigenereEngine::<IncompleteAscii>::try_new(keyword).unwrap();
+            let encrypted_message = engine.encrypt(&message);
+
+            let key_lengths = estimate_key_length(&encrypted_message, 20);
+            assert_eq!(key_lengths.len(), 20);
+            assert_eq!(key_lengths[0].length, keyword.len());
+            let total_confidence = key_lengths.iter().map(|k| k.confidence).sum::<f64>();
+            assert!((total_confidence - 1.0).abs() < 1e-9);
+
+            let candidates = crack_vigenere(&encrypted_message, 20);
+            assert_eq!(candidates[0].keyword, keyword);
+            assert_eq!(candidates[0].clear_text, message);
+            assert!(candidates
+                .windows(2)
+                .all(|c| c[0].confidence >= c[1].confidence));
+        }
+    }
+
+    #[test]
+    fn foreign_characters_are_ignored() {
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+            GALLIC_WAR.to_lowercase(),
+            crate::ForeignCharPolicy::Passthrough,
+        )
+        .unwrap();
+        let engine = VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("gallia").unwrap();
+
+        let candidates = crack_vigenere(&engine.encrypt(&message), 15);
+        assert_eq!(candidates[0].keyword, "gallia");
+        assert_eq!(candidates[0].clear_text, message);
+    }
+
+    #[test]
+    fn substitution_solver() {
+        use crate::substitution::SubstitutionEngine;
+
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+            "when the storm finally passed the fishermen went down to the harbour to count \
+             their boats and found that only two had been lost, which was a great relief to \
+             everyone in the village because the winter had already been hard",
+            crate::ForeignCharPolicy::Passthrough,
+        )
+        .unwrap();
+        let engine = SubstitutionEngine::<AsciiLowerCaseAlphabet>::random(2024);
+        let encrypted_message = engine.encrypt(&message);
+
+        let solver = SubstitutionSolver::for_language(Language::English);
+        let candidate = solver.solve(&encrypted_message);
+        assert_eq!(candidate.clear_text, message);
+        let key =
+            SubstitutionEngine::<AsciiLowerCaseAlphabet>::try_from_letters(candidate.key).unwrap();
+        assert_eq!(key.decrypt(&encrypted_message), message);
+        assert_eq!(solver.solve(&encrypted_message).score, candidate.score);
+
+        // Without iterations, the key only matches the letters by frequency
+        let solver = solver.with_restarts(0).with_iterations(0);
+        assert!(solver.solve(&encrypted_message).score > candidate.score);
+    }
+
+    #[test]
+    fn keyword_periods() {
+        assert_eq!(shortest_period(&['a', 'b', 'a', 'b']), &['a', 'b']);
+        assert_eq!(shortest_period(&['a', 'b', 'a']), &['a', 'b', 'a']);
+        assert_eq!(shortest_period(&['a', 'a', 'a']), &['a']);
+        assert_eq!(
+            repeated_sequence_distances(&[1, 2, 3, 0, 1, 2, 3, 1, 2, 3]),
+            [4, 3]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Encryption with alphabets chosen at runtime
+//!
+//! The types of this module mirror [CaesarEngine](crate::CaesarEngine), but work with a
+//! [DynamicAlphabet] value instead of an [Alphabet](crate::alphabets::Alphabet) type.
+//! As the alphabet is not known at compile time, messages are plain strings that are
+//! validated against the engine's alphabet when they are encrypted or decrypted,
+//! according to the engine's [ForeignCharPolicy].
+use alloc::string::String;
+
+use crate::alphabets::DynamicAlphabet;
+use crate::table::Substitution;
+use crate::{CharacterNotInAlphabet, ForeignCharPolicy, Shift};
+
+/// Struct that encrypts and decrypts message in a [DynamicAlphabet].
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::DynamicAlphabet;
+/// use caesar_cipher::dynamic::DynamicCaesarEngine;
+/// use caesar_cipher::{CharacterNotInAlphabet, ForeignCharPolicy, Shift};
+///
+/// let alphabet = DynamicAlphabet::try_new("abcdefghijklmnopqrstuvwxyz ").unwrap();
+/// let engine = DynamicCaesarEngine::new(alphabet, Shift(3));
+///
+/// let encrypted_message = engine.encrypt("hello world").unwrap();
+/// assert_eq!(encrypted_message, "khoorczruog");
+/// assert_eq!(engine.decrypt(&encrypted_message).unwrap(), "hello world");
+///
+/// assert_eq!(engine.encrypt("Hello"), Err(CharacterNotInAlphabet::new('H', 0, 0)));
+///
+/// let engine = engine.with_policy(ForeignCharPolicy::Passthrough);
+/// assert_eq!(engine.encrypt("Hello!").unwrap(), "Hhoor!");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DynamicCaesarEngine {
+    alphabet: DynamicAlphabet,
+    substitution: Substitution,
+    policy: ForeignCharPolicy,
+}
+
+impl DynamicCaesarEngine {
+    /// Creates a new engine for the given alphabet and shift
+    pub fn new(alphabet: DynamicAlphabet, shift: Shift) -> Self {
+        let substitution = Substitution::shift(alphabet.letters(), shift);
+        Self {
+            alphabet,
+            substitution,
+            policy: ForeignCharPolicy::Reject,
+        }
+    }
+
+    /// Creates a new engine that shifts letters regardless of their case,
+    /// and keeps the case of each letter in the output
+    ///
+    /// See [CaesarEngine::new_preserving_case](crate::CaesarEngine::new_preserving_case).
+    pub fn new_preserving_case(alphabet: DynamicAlphabet, shift: Shift) -> Self {
+        let substitution = Substitution::shift_preserving_case(alphabet.letters(), shift);
+        Self {
+            alphabet,
+            substitution,
+            policy: ForeignCharPolicy::Reject,
+        }
+    }
+
+    /// Sets how characters that are not in the alphabet are handled,
+    /// they are rejected by default
+    pub fn with_policy(mut self, policy: ForeignCharPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The alphabet the engine works in
+    pub fn alphabet(&self) -> &DynamicAlphabet {
+        &self.alphabet
+    }
+
+    pub fn encrypt(&self, clear_message: &str) -> Result<String, CharacterNotInAlphabet> {
+        self.substitution.encrypt(clear_message, self.policy)
+    }
+
+    pub fn decrypt(&self, cipher_message: &str) -> Result<String, CharacterNotInAlphabet> {
+        self.substitution.decrypt(cipher_message, self.policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::{Alphabet, AsciiLowerCaseAlphabet, IncompleteAscii};
+    use crate::{CaesarEngine, ClearText};
+
+    #[test]
+    fn same_result_as_static_alphabet() {
+        let alphabet =
+            DynamicAlphabet::try_from_letters(IncompleteAscii::letters().iter().copied()).unwrap();
+        let dynamic_engine = DynamicCaesarEngine::new(alphabet, Shift(17));
+        let static_engine = CaesarEngine::<IncompleteAscii>::new(Shift(17));
+
+        let message = "Ave Imperator, morituri te salutant";
+        let encrypted_message = dynamic_engine.encrypt(message).unwrap();
+        let expected = static_engine.encrypt(&ClearText::try_new(message).unwrap());
+        assert_eq!(&expected, encrypted_message.as_str());
+
+        assert_eq!(dynamic_engine.decrypt(&encrypted_message).unwrap(), message);
+    }
+
+    #[test]
+    fn placeholders_are_encrypted_like_in_texts() {
+        let alphabet = DynamicAlphabet::try_from_letters('a'..='z').unwrap();
+        let static_engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+        for placeholder in ['x', '_'] {
+            let policy = ForeignCharPolicy::Replace(placeholder);
+            let dynamic_engine =
+                DynamicCaesarEngine::new(alphabet.clone(), Shift(3)).with_policy(policy);
+
+            let encrypted_message = dynamic_engine.encrypt("a b").unwrap();
+            let expected =
+                static_engine.encrypt(&ClearText::try_new_with_policy("a b", policy).unwrap());
+            assert_eq!(&expected, encrypted_message.as_str());
+
+            let decrypted_message = dynamic_engine.decrypt(&encrypted_message).unwrap();
+            assert_eq!(decrypted_message, format!("a{placeholder}b"));
+        }
+    }
+}
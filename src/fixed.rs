@@ -0,0 +1,174 @@
+//! Encryption without an allocator
+//!
+//! [FixedCaesarEngine] works on string slices and writes its output in a buffer
+//! provided by the caller, so it is available without the `alloc` feature.
+//! It does not build lookup tables either: each letter is searched in
+//! [Alphabet::letters], so encrypting a letter takes longer with large alphabets
+//! than with [CaesarEngine](crate::CaesarEngine).
+use crate::alphabets::Alphabet;
+use crate::{CharacterNotInAlphabet, ForeignCharPolicy, Shift};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BufferError {
+    /// The message contains a character that is not in the alphabet
+    NotInAlphabet(CharacterNotInAlphabet),
+    /// The output does not fit in the buffer, which needs `required` bytes
+    TooSmall { required: usize },
+}
+
+impl From<CharacterNotInAlphabet> for BufferError {
+    fn from(error: CharacterNotInAlphabet) -> Self {
+        Self::NotInAlphabet(error)
+    }
+}
+
+/// Struct that encrypts and decrypts message into caller-provided buffers.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::fixed::{BufferError, FixedCaesarEngine};
+/// use caesar_cipher::{ForeignCharPolicy, Shift};
+///
+/// let engine = FixedCaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3))
+///     .with_policy(ForeignCharPolicy::Passthrough);
+///
+/// let mut buffer = [0; 16];
+/// let encrypted_message = engine.encrypt("hello, world", &mut buffer).unwrap();
+/// assert_eq!(encrypted_message, "khoor, zruog");
+///
+/// let mut decrypted = [0; 16];
+/// let decrypted_message = engine.decrypt(encrypted_message, &mut decrypted).unwrap();
+/// assert_eq!(decrypted_message, "hello, world");
+///
+/// assert_eq!(
+///     engine.encrypt("hello, world", &mut [0; 4]),
+///     Err(BufferError::TooSmall { required: 12 })
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FixedCaesarEngine<A: Alphabet> {
+    _marker: core::marker::PhantomData<A>,
+    /// The shift, in `0..A::letters().len()`
+    shift: usize,
+    policy: ForeignCharPolicy,
+}
+
+impl<A: Alphabet> FixedCaesarEngine<A> {
+    /// Creates a new engine for the given shift, characters that are
+    /// not in the alphabet are rejected by default
+    pub fn new(shift: Shift) -> Self {
+        Self {
+            _marker: Default::default(),
+            shift: shift.reduce(A::letters().len()),
+            policy: ForeignCharPolicy::Reject,
+        }
+    }
+
+    /// Sets how characters that are not in the alphabet are handled
+    pub fn with_policy(mut self, policy: ForeignCharPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Encrypts the message into the buffer, and returns the encrypted part of the buffer
+    pub fn encrypt<'b>(&self, message: &str, buffer: &'b mut [u8]) -> Result<&'b str, BufferError> {
+        self.apply(message, self.shift, buffer)
+    }
+
+    /// Decrypts the message into the buffer, and returns the decrypted part of the buffer
+    pub fn decrypt<'b>(&self, cipher: &str, buffer: &'b mut [u8]) -> Result<&'b str, BufferError> {
+        let alphabet_len = A::letters().len();
+        self.apply(cipher, (alphabet_len - self.shift) % alphabet_len, buffer)
+    }
+
+    /// Shifts each letter of the input by `shift`
+    ///
+    /// The whole input is read even if the buffer is too small,
+    /// so that the error gives the size the buffer should have.
+    fn apply<'b>(
+        &self,
+        input: &str,
+        shift: usize,
+        buffer: &'b mut [u8],
+    ) -> Result<&'b str, BufferError> {
+        let alphabet = A::letters();
+        let mut len = 0;
+        let shifted = |letter| {
+            let letter_index = alphabet.iter().position(|&c| c == letter)?;
+            Some(alphabet[(letter_index + shift) % alphabet.len()])
+        };
+        for (index, (byte_offset, letter)) in input.char_indices().enumerate() {
+            let output = match shifted(letter) {
+                Some(shifted_letter) => shifted_letter,
+                None => match self.policy.replacement(CharacterNotInAlphabet::new(
+                    letter,
+                    index,
+                    byte_offset,
+                ))? {
+                    Some(replacement) => shifted(replacement).unwrap_or(replacement),
+                    None => continue,
+                },
+            };
+            let end = len + output.len_utf8();
+            if let Some(slot) = buffer.get_mut(len..end) {
+                output.encode_utf8(slot);
+            }
+            len = end;
+        }
+
+        if len > buffer.len() {
+            return Err(BufferError::TooSmall { required: len });
+        }
+        Ok(core::str::from_utf8(&buffer[..len]).expect("only whole characters are written"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::{GreekAlphabet, IncompleteAscii};
+
+    #[test]
+    fn same_as_caesar_engine() {
+        let message = "Ave Imperator, morituri te salutant";
+        let engine = FixedCaesarEngine::<IncompleteAscii>::new(Shift(-43));
+
+        let mut buffer = [0; 64];
+        let encrypted_message = engine.encrypt(message, &mut buffer).unwrap();
+        #[cfg(feature = "alloc")]
+        {
+            let clear_text = crate::ClearText::<IncompleteAscii>::try_new(message).unwrap();
+            let caesar_engine = crate::CaesarEngine::<IncompleteAscii>::new(Shift(-43));
+            assert_eq!(caesar_engine.encrypt(&clear_text), *encrypted_message);
+        }
+
+        let mut decrypted = [0; 64];
+        assert_eq!(
+            engine.decrypt(encrypted_message, &mut decrypted).unwrap(),
+            message
+        );
+    }
+
+    #[test]
+    fn buffer_errors() {
+        let engine = FixedCaesarEngine::<GreekAlphabet>::new(Shift(1));
+
+        // Each Greek letter takes two bytes
+        let mut buffer = [0; 7];
+        assert_eq!(
+            engine.encrypt("αβγδ", &mut buffer),
+            Err(BufferError::TooSmall { required: 8 })
+        );
+        assert_eq!(
+            engine.encrypt("αβ γδ", &mut buffer),
+            Err(BufferError::NotInAlphabet(CharacterNotInAlphabet::new(
+                ' ', 2, 4
+            )))
+        );
+
+        let engine = engine.with_policy(ForeignCharPolicy::Drop);
+        assert_eq!(engine.encrypt("αβ γ", &mut buffer), Ok("βγδ"));
+    }
+}
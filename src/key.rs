@@ -0,0 +1,477 @@
+//! Keys of every cipher of the crate, and the text format of key files
+//!
+//! A [Key] holds the engine of one of the ciphers, so it is tied to an [Alphabet]
+//! and always valid for it. It is written in a key file with the
+//! [name](Alphabet::name) of its alphabet, and reading the file with another
+//! alphabet is an error, even if the key happens to be valid in both. Alphabets
+//! implemented by hand must [override its name](Alphabet::name) for key files to be
+//! read by programs built with another compiler.
+//!
+//! # Format
+//!
+//! A key file is a text file with three lines: the version of the format, the
+//! name of the alphabet, and the key itself, whose first word is the cipher.
+//!
+//! ```text
+//! caesar-cipher-key 1
+//! alphabet "incomplete-ascii"
+//! vigenere "Ave Caesar"
+//! ```
+//!
+//! The key is one of:
+//!
+//! - `caesar 3`, or `caesar-preserving-case 3` for an engine created with
+//!   [CaesarEngine::new_preserving_case], with the shift, possibly negative.
+//! - `vigenere "lemon"`, with the keyword.
+//! - `affine 5 8`, with the `(a, b)` encryption key.
+//! - `substitution "zebrascdfghijklmnopqtuvwxy"`, with the replacement of each letter
+//!   of the alphabet.
+//!
+//! Strings are written between double quotes, with `\"`, `\\`, `\n`, `\r` and `\t`
+//! escapes. Empty lines and lines starting with `#` are ignored.
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str::FromStr;
+
+use crate::affine::{AffineEngine, InvalidAffineKey};
+use crate::alphabets::Alphabet;
+use crate::substitution::{InvalidSubstitutionKey, SubstitutionEngine};
+use crate::vigenere::{InvalidKeyword, VigenereEngine};
+use crate::{CaesarEngine, Cipher, CipherText, ClearText, Shift};
+
+/// First word of the key files
+const HEADER: &str = "caesar-cipher-key";
+/// Version of the format written in the key files
+const VERSION: u32 = 1;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum InvalidKeyFile {
+    /// The file does not start with the `caesar-cipher-key` line
+    MissingHeader,
+    /// The file is written in a version of the format that is not supported
+    UnsupportedVersion(String),
+    /// The line is not in the format, a missing line is reported one past the last line
+    Syntax {
+        line: usize,
+    },
+    /// The cipher of the key is not known
+    UnknownCipher(String),
+    /// The key is for another alphabet than the expected one
+    WrongAlphabet {
+        expected: &'static str,
+        found: String,
+    },
+    InvalidKeyword(InvalidKeyword),
+    InvalidAffineKey(InvalidAffineKey),
+    InvalidSubstitutionKey(InvalidSubstitutionKey),
+}
+
+impl From<InvalidKeyword> for InvalidKeyFile {
+    fn from(error: InvalidKeyword) -> Self {
+        Self::InvalidKeyword(error)
+    }
+}
+
+impl From<InvalidAffineKey> for InvalidKeyFile {
+    fn from(error: InvalidAffineKey) -> Self {
+        Self::InvalidAffineKey(error)
+    }
+}
+
+impl From<InvalidSubstitutionKey> for InvalidKeyFile {
+    fn from(error: InvalidSubstitutionKey) -> Self {
+        Self::InvalidSubstitutionKey(error)
+    }
+}
+
+/// The key of one of the ciphers, for the alphabet `A`
+///
+/// Keys are written in key files with [ToString], and read with [FromStr], see the
+/// [module documentation](self) for the format. With the `serde` feature, they are
+/// also serialized along with the name of their alphabet, see the
+/// [serialization](crate::serialization) module.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+/// use caesar_cipher::key::{InvalidKeyFile, Key};
+/// use caesar_cipher::vigenere::VigenereEngine;
+/// use caesar_cipher::{Cipher, ClearText};
+///
+/// let key = Key::from(VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("lemon").unwrap());
+/// let key_file = key.to_string();
+/// assert_eq!(
+///     key_file,
+///     "caesar-cipher-key 1\nalphabet \"ascii-lowercase\"\nvigenere \"lemon\"\n"
+/// );
+///
+/// let key = key_file.parse::<Key<AsciiLowerCaseAlphabet>>().unwrap();
+/// let message = ClearText::try_new("attackatdawn").unwrap();
+/// assert_eq!(&key.encrypt(&message), "lxfopvefrnhr");
+///
+/// assert_eq!(
+///     key_file.parse::<Key<IncompleteAscii>>().err(),
+///     Some(InvalidKeyFile::WrongAlphabet {
+///         expected: "incomplete-ascii",
+///         found: "ascii-lowercase".to_string(),
+///     })
+/// );
+/// ```
+pub enum Key<A: Alphabet> {
+    Caesar(CaesarEngine<A>),
+    Vigenere(VigenereEngine<A>),
+    Affine(AffineEngine<A>),
+    Substitution(SubstitutionEngine<A>),
+}
+
+impl<A: Alphabet> Key<A> {
+    /// The name of the cipher, as written in key files
+    pub fn cipher_name(&self) -> &'static str {
+        match self {
+            Self::Caesar(engine) if engine.preserves_case() => "caesar-preserving-case",
+            Self::Caesar(_) => "caesar",
+            Self::Vigenere(_) => "vigenere",
+            Self::Affine(_) => "affine",
+            Self::Substitution(_) => "substitution",
+        }
+    }
+
+    fn cipher(&self) -> &dyn Cipher<A> {
+        match self {
+            Self::Caesar(engine) => engine,
+            Self::Vigenere(engine) => engine,
+            Self::Affine(engine) => engine,
+            Self::Substitution(engine) => engine,
+        }
+    }
+
+    /// The cipher and the key, as in the last line of key files
+    #[cfg(feature = "serde")]
+    pub(crate) fn key_line(&self) -> String {
+        struct KeyLine<'k, A: Alphabet>(&'k Key<A>);
+
+        impl<A: Alphabet> fmt::Display for KeyLine<'_, A> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.write_key(f)
+            }
+        }
+
+        KeyLine(self).to_string()
+    }
+
+    /// Reads the cipher and the key from the last line of a key file,
+    /// the line number is used in syntax errors
+    pub(crate) fn parse_key_line(key: &str, line: usize) -> Result<Self, InvalidKeyFile> {
+        let syntax_error = || InvalidKeyFile::Syntax { line };
+        let mut words = key.splitn(2, ' ');
+        let cipher = words.next().unwrap_or_default();
+        let arguments = words.next().ok_or_else(syntax_error)?;
+        let shift = || arguments.parse::<isize>().map(Shift);
+        let key = match cipher {
+            "caesar" => Self::Caesar(CaesarEngine::new(shift().map_err(|_| syntax_error())?)),
+            "caesar-preserving-case" => Self::Caesar(CaesarEngine::new_preserving_case(
+                shift().map_err(|_| syntax_error())?,
+            )),
+            "vigenere" => {
+                let keyword = parse_quoted(arguments).ok_or_else(syntax_error)?;
+                Self::Vigenere(VigenereEngine::try_new(keyword)?)
+            }
+            "affine" => {
+                let mut numbers = arguments.split(' ').map(str::parse::<usize>);
+                match (numbers.next(), numbers.next(), numbers.next()) {
+                    (Some(Ok(a)), Some(Ok(b)), None) => Self::Affine(AffineEngine::try_new(a, b)?),
+                    _ => return Err(syntax_error()),
+                }
+            }
+            "substitution" => {
+                let key = parse_quoted(arguments).ok_or_else(syntax_error)?;
+                Self::Substitution(SubstitutionEngine::try_new(key)?)
+            }
+            _ => return Err(InvalidKeyFile::UnknownCipher(cipher.to_string())),
+        };
+        Ok(key)
+    }
+
+    /// Writes the cipher and the key, as in the last line of key files
+    fn write_key(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cipher_name())?;
+        match self {
+            Self::Caesar(engine) => write!(f, " {}", engine.shift().0),
+            Self::Vigenere(engine) => write_quoted(f, engine.keyword().chars()),
+            Self::Affine(engine) => {
+                let (a, b) = engine.encryption_key();
+                write!(f, " {a} {b}")
+            }
+            Self::Substitution(engine) => write_quoted(f, engine.key().iter().copied()),
+        }
+    }
+}
+
+impl<A: Alphabet> From<CaesarEngine<A>> for Key<A> {
+    fn from(engine: CaesarEngine<A>) -> Self {
+        Self::Caesar(engine)
+    }
+}
+
+impl<A: Alphabet> From<VigenereEngine<A>> for Key<A> {
+    fn from(engine: VigenereEngine<A>) -> Self {
+        Self::Vigenere(engine)
+    }
+}
+
+impl<A: Alphabet> From<AffineEngine<A>> for Key<A> {
+    fn from(engine: AffineEngine<A>) -> Self {
+        Self::Affine(engine)
+    }
+}
+
+impl<A: Alphabet> From<SubstitutionEngine<A>> for Key<A> {
+    fn from(engine: SubstitutionEngine<A>) -> Self {
+        Self::Substitution(engine)
+    }
+}
+
+impl<A: Alphabet> Cipher<A> for Key<A> {
+    fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        self.cipher().encrypt(clear_message)
+    }
+
+    fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        self.cipher().decrypt(cipher_message)
+    }
+}
+
+impl<A: Alphabet> fmt::Debug for Key<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Key<{}>(", A::name())?;
+        self.write_key(f)?;
+        write!(f, ")")
+    }
+}
+
+impl<A: Alphabet> fmt::Display for Key<A> {
+    /// Writes the key file
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER} {VERSION}")?;
+        write!(f, "alphabet")?;
+        write_quoted(f, A::name().chars())?;
+        writeln!(f)?;
+        self.write_key(f)?;
+        writeln!(f)
+    }
+}
+
+impl<A: Alphabet> FromStr for Key<A> {
+    type Err = InvalidKeyFile;
+
+    /// Reads a key file
+    fn from_str(key_file: &str) -> Result<Self, Self::Err> {
+        let file = KeyFile::parse(key_file)?;
+        if file.alphabet != A::name() {
+            return Err(InvalidKeyFile::WrongAlphabet {
+                expected: A::name(),
+                found: file.alphabet,
+            });
+        }
+
+        Self::parse_key_line(file.key, file.key_line)
+    }
+}
+
+/// Reads the name of the alphabet of a key file, to know which alphabet to read it with
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::key::key_file_alphabet;
+///
+/// let key_file = concat!(
+///     "caesar-cipher-key 1\n",
+///     "# Issued for the legions of Gaul\n",
+///     "alphabet \"incomplete-ascii\"\n",
+///     "caesar -3\n",
+/// );
+/// assert_eq!(key_file_alphabet(key_file).unwrap(), "incomplete-ascii");
+/// ```
+pub fn key_file_alphabet(key_file: &str) -> Result<String, InvalidKeyFile> {
+    KeyFile::parse(key_file).map(|file| file.alphabet)
+}
+
+/// The lines of a key file, before reading the key
+struct KeyFile<'a> {
+    alphabet: String,
+    /// The last line, with the cipher and the key
+    key: &'a str,
+    /// Number of the last line, from 1
+    key_line: usize,
+}
+
+impl<'a> KeyFile<'a> {
+    fn parse(key_file: &'a str) -> Result<Self, InvalidKeyFile> {
+        let mut lines = key_file
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.strip_suffix('\r').unwrap_or(line)))
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+        let line_count = key_file.lines().count();
+        let missing_line = || InvalidKeyFile::Syntax {
+            line: line_count + 1,
+        };
+
+        let (_, header) = lines.next().ok_or(InvalidKeyFile::MissingHeader)?;
+        match header.split_once(' ') {
+            Some((HEADER, version)) if version == VERSION.to_string() => {}
+            Some((HEADER, version)) => {
+                return Err(InvalidKeyFile::UnsupportedVersion(version.to_string()))
+            }
+            _ => return Err(InvalidKeyFile::MissingHeader),
+        }
+
+        let (alphabet_line, alphabet) = lines.next().ok_or_else(missing_line)?;
+        let alphabet = alphabet
+            .strip_prefix("alphabet ")
+            .and_then(parse_quoted)
+            .ok_or(InvalidKeyFile::Syntax {
+                line: alphabet_line,
+            })?;
+
+        let (key_line, key) = lines.next().ok_or_else(missing_line)?;
+        if let Some((line, _)) = lines.next() {
+            return Err(InvalidKeyFile::Syntax { line });
+        }
+        Ok(Self {
+            alphabet,
+            key,
+            key_line,
+        })
+    }
+}
+
+/// Writes a space and the characters between double quotes, escaping
+/// the characters that would end the string or the line
+fn write_quoted(f: &mut fmt::Formatter<'_>, chars: impl Iterator<Item = char>) -> fmt::Result {
+    write!(f, " \"")?;
+    for c in chars {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Reads a string written by [write_quoted], which must be the whole input
+fn parse_quoted(quoted: &str) -> Option<String> {
+    let mut chars = quoted.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut string = String::new();
+    while let Some(c) = chars.next() {
+        string.push(match c {
+            '\\' => match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                _ => return None,
+            },
+            '"' => return None,
+            c => c,
+        });
+    }
+    Some(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+
+    #[test]
+    fn every_key_is_read_back() {
+        let keys: [Key<IncompleteAscii>; 5] = [
+            CaesarEngine::new(Shift(-3)).into(),
+            CaesarEngine::new_preserving_case(Shift(61)).into(),
+            VigenereEngine::try_new("Ave, Caesar!").unwrap().into(),
+            AffineEngine::try_new(7, 70).unwrap().into(),
+            SubstitutionEngine::random(42).into(),
+        ];
+        let message = ClearText::<IncompleteAscii>::try_new("Alea iacta est.").unwrap();
+        for key in keys {
+            let key_file = key.to_string();
+            let read_key = key_file.parse::<Key<IncompleteAscii>>().unwrap();
+            assert_eq!(read_key.to_string(), key_file);
+            assert_eq!(read_key.encrypt(&message), key.encrypt(&message));
+        }
+
+        let key_file = Key::from(AffineEngine::<IncompleteAscii>::try_new(7, 70).unwrap());
+        assert_eq!(
+            key_file.to_string().lines().last(),
+            Some("affine 7 10"),
+            "the key is reduced modulo the alphabet length"
+        );
+    }
+
+    #[test]
+    fn quoted_strings() {
+        assert_eq!(
+            parse_quoted(r#""say \"ave\"\\\n\r\t""#).as_deref(),
+            Some("say \"ave\"\\\n\r\t")
+        );
+        assert_eq!(parse_quoted(r#""a"b""#), None);
+        assert_eq!(parse_quoted(r#""a\b""#), None);
+        assert_eq!(parse_quoted("\"a"), None);
+    }
+
+    #[test]
+    fn invalid_key_files() {
+        let parse = |key_file: &str| key_file.parse::<Key<AsciiLowerCaseAlphabet>>().err();
+        assert_eq!(parse(""), Some(InvalidKeyFile::MissingHeader));
+        assert_eq!(
+            parse("caesar-cipher-key 2\nalphabet \"ascii-lowercase\"\ncaesar 3"),
+            Some(InvalidKeyFile::UnsupportedVersion("2".to_string()))
+        );
+        assert_eq!(
+            parse("caesar-cipher-key 1\r\nalphabet \"ascii-lowercase\"\r\n"),
+            Some(InvalidKeyFile::Syntax { line: 3 })
+        );
+        assert_eq!(
+            parse("caesar-cipher-key 1\n\nalphabet ascii-lowercase\ncaesar 3"),
+            Some(InvalidKeyFile::Syntax { line: 3 })
+        );
+        assert_eq!(
+            parse("caesar-cipher-key 1\nalphabet \"ascii-lowercase\"\ncaesar three"),
+            Some(InvalidKeyFile::Syntax { line: 3 })
+        );
+        assert_eq!(
+            parse("caesar-cipher-key 1\nalphabet \"ascii-lowercase\"\nenigma \"I II III\""),
+            Some(InvalidKeyFile::UnknownCipher("enigma".to_string()))
+        );
+        assert_eq!(
+            parse("caesar-cipher-key 1\nalphabet \"ascii-lowercase\"\naffine 2 3"),
+            Some(InvalidKeyFile::InvalidAffineKey(
+                InvalidAffineKey::NotCoprime {
+                    a: 2,
+                    alphabet_len: 26
+                }
+            ))
+        );
+        assert_eq!(
+            parse("caesar-cipher-key 1\nalphabet \"ascii-lowercase\"\nvigenere \"\""),
+            Some(InvalidKeyFile::InvalidKeyword(InvalidKeyword::Empty))
+        );
+        assert_eq!(
+            parse("caesar-cipher-key 1\nalphabet \"ascii-lowercase\"\nsubstitution \"abc\""),
+            Some(InvalidKeyFile::InvalidSubstitutionKey(
+                InvalidSubstitutionKey::WrongLength {
+                    expected: 26,
+                    found: 3
+                }
+            ))
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Models of what texts of a language look like, to score candidate plaintexts
+//!
+//! A [LanguageModel] gives a score to a text, lower is better, so that the
+//! decryption attempts that look the most like the language come first. There
+//! are three kinds of models:
+//!
+//! - [UnigramModel]: the frequencies of the letters, compared with a chi-squared
+//!   statistic. It works even on short texts, and is the statistic [crack](crate::crack::crack)
+//!   uses for English.
+//! - [NGramModel]: the log-probabilities of sequences of letters, usually bigrams
+//!   or quadgrams, which tell apart texts with the same letters in a different order.
+//! - [DictionaryModel]: the proportion of letters that belong to a known word.
+//!
+//! The models are loaded from frequency tables: text files with one entry per line,
+//! followed by its count, as in `the 123`. Empty lines and lines starting with `#`
+//! are ignored. Tables of English, French, German, Spanish and Latin are embedded in
+//! the crate, see [Language].
+//!
+//! The embedded tables only have the letters `a` to `z`, accents being removed, and
+//! the models read texts regardless of case, so they apply to the texts of both
+//! [AsciiLowerCaseAlphabet](crate::alphabets::AsciiLowerCaseAlphabet) and
+//! [IncompleteAscii](crate::alphabets::IncompleteAscii).
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A model of a language, which scores texts by how far they are from it
+pub trait LanguageModel {
+    /// How far the text is from the language, lower is better
+    fn score(&self, text: &str) -> f64;
+}
+
+/// Functions like [english_chi_squared](crate::crack::english_chi_squared) are models
+impl<F: Fn(&str) -> f64> LanguageModel for F {
+    fn score(&self, text: &str) -> f64 {
+        self(text)
+    }
+}
+
+/// Two models together score texts with the sum of their scores
+impl<M: LanguageModel, N: LanguageModel> LanguageModel for (M, N) {
+    fn score(&self, text: &str) -> f64 {
+        self.0.score(text) + self.1.score(text)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum InvalidTable {
+    /// The line is not an entry followed by a finite and non-negative count
+    Syntax { line: usize },
+    /// The entry of the line does not have the same number of letters as the others
+    Length { line: usize },
+    /// The table has no entry with a positive count
+    Empty,
+}
+
+/// Reads the entries of a frequency table with their line, numbered from 1, and their count
+fn parse_table(table: &str) -> Result<Vec<(usize, &str, f64)>, InvalidTable> {
+    let mut entries = Vec::new();
+    for (index, line) in table.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let syntax_error = InvalidTable::Syntax { line: index + 1 };
+        let mut fields = line.split_whitespace();
+        let (Some(entry), Some(count), None) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(syntax_error);
+        };
+        match count.parse::<f64>() {
+            Ok(count) if count.is_finite() && count >= 0.0 => {
+                entries.push((index + 1, entry, count))
+            }
+            _ => return Err(syntax_error),
+        }
+    }
+    if entries.iter().all(|&(_, _, count)| count == 0.0) {
+        return Err(InvalidTable::Empty);
+    }
+    Ok(entries)
+}
+
+/// Checks that all the entries have `len` letters
+fn check_entry_len(entries: &[(usize, &str, f64)], len: usize) -> Result<(), InvalidTable> {
+    match entries
+        .iter()
+        .find(|(_, entry, _)| entry.chars().count() != len)
+    {
+        Some(&(line, _, _)) => Err(InvalidTable::Length { line }),
+        None => Ok(()),
+    }
+}
+
+/// The text in lowercase, without whitespaces
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The byte offsets of the characters of the text, followed by its length
+fn char_boundaries(text: &str) -> Vec<usize> {
+    text.char_indices()
+        .map(|(offset, _)| offset)
+        .chain([text.len()])
+        .collect()
+}
+
+/// Base 10 logarithm of a positive normal number, which `core` does not provide
+fn log10(x: f64) -> f64 {
+    // x is mantissa * 2^exponent, with the mantissa in [1, 2)
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mantissa = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+    // ln(mantissa) = 2 * atanh(z) = 2 * (z + z^3 / 3 + z^5 / 5 + ...), with |z| < 1/3
+    let z = (mantissa - 1.0) / (mantissa + 1.0);
+    let mut power = z;
+    let mut atanh = 0.0;
+    for k in (1..40).step_by(2) {
+        atanh += power / k as f64;
+        power *= z * z;
+    }
+    exponent as f64 * core::f64::consts::LOG10_2 + 2.0 * atanh * core::f64::consts::LOG10_E
+}
+
+/// Frequencies of the letters of a language
+///
+/// Texts are scored with the [chi-squared statistic](https://en.wikipedia.org/wiki/Chi-squared_test)
+/// of their letters against these frequencies, the same way as
+/// [english_chi_squared](crate::crack::english_chi_squared): letters are counted
+/// regardless of their case, and every character that is neither a whitespace
+/// nor in the table counts as a letter that never occurs in the language.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::language::{LanguageModel, UnigramModel};
+///
+/// let model = UnigramModel::from_table("a 2\nb 1\n# No c\nc 0").unwrap();
+/// assert!(model.score("aab") < model.score("abb"));
+/// assert_eq!(model.score("aabc"), model.score("aab?"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct UnigramModel {
+    /// The letters and their frequencies, sorted by letter
+    frequencies: Vec<(char, f64)>,
+}
+
+impl UnigramModel {
+    /// Loads the model from a table of letters, entries with a count of zero
+    /// being left out
+    pub fn from_table(table: &str) -> Result<Self, InvalidTable> {
+        let entries = parse_table(table)?;
+        check_entry_len(&entries, 1)?;
+
+        let total: f64 = entries.iter().map(|&(_, _, count)| count).sum();
+        let mut counts = BTreeMap::<char, f64>::new();
+        for (_, letter, count) in entries.into_iter().filter(|&(_, _, count)| count > 0.0) {
+            let letter = letter.chars().next().expect("entries have one letter");
+            *counts.entry(letter).or_default() += count;
+        }
+        let frequencies = counts
+            .into_iter()
+            .map(|(letter, count)| (letter, count / total))
+            .collect();
+        Ok(Self { frequencies })
+    }
+
+    /// The frequency of the letter, regardless of its case, zero if it is not in the table
+    pub fn frequency(&self, letter: char) -> f64 {
+        let letter = letter.to_lowercase().next().unwrap_or(letter);
+        self.frequencies
+            .binary_search_by_key(&letter, |&(letter, _)| letter)
+            .map_or(0.0, |index| self.frequencies[index].1)
+    }
+}
+
+impl LanguageModel for UnigramModel {
+    fn score(&self, text: &str) -> f64 {
+        let mut letter_counts = alloc::vec![0usize; self.frequencies.len()];
+        let mut total = 0usize;
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            total += 1;
+            let c = c.to_lowercase().next().unwrap_or(c);
+            if let Ok(index) = self
+                .frequencies
+                .binary_search_by_key(&c, |&(letter, _)| letter)
+            {
+                letter_counts[index] += 1;
+            }
+        }
+        if total == 0 {
+            return f64::INFINITY;
+        }
+
+        letter_counts
+            .iter()
+            .zip(&self.frequencies)
+            .map(|(&observed, &(_, frequency))| {
+                let expected = frequency * total as f64;
+                let difference = observed as f64 - expected;
+                difference * difference / expected
+            })
+            .sum()
+    }
+}
+
+/// Log-probabilities of the sequences of `n` letters of a language
+///
+/// Texts are scored with the opposite of the mean base 10 logarithm of the probability
+/// of their sequences of letters, once the whitespaces are removed. Sequences that are
+/// not in the table, like those with characters that are not letters, get the
+/// probability of a hundredth of an occurrence.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::language::{Language, LanguageModel};
+///
+/// let model = Language::English.quadgrams();
+/// assert_eq!(model.n(), 4);
+///
+/// // The same letters, in a different order
+/// let text = "the general sent his legions across the river";
+/// assert!(model.score(text) < model.score("eth eganerl tens ish glenois crosas eth rerive"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NGramModel {
+    /// The number of letters of the sequences
+    n: usize,
+    log_probabilities: BTreeMap<String, f64>,
+    /// The log-probability of the sequences that are not in the table
+    floor: f64,
+}
+
+impl NGramModel {
+    /// Loads the model from a table of sequences that all have the same number of letters
+    pub fn from_table(table: &str) -> Result<Self, InvalidTable> {
+        let entries = parse_table(table)?;
+        let n = entries[0].1.chars().count();
+        check_entry_len(&entries, n)?;
+
+        let total: f64 = entries.iter().map(|&(_, _, count)| count).sum();
+        let mut log_probabilities = BTreeMap::<String, f64>::new();
+        for (_, sequence, count) in entries.into_iter().filter(|&(_, _, count)| count > 0.0) {
+            *log_probabilities.entry(String::from(sequence)).or_default() += count;
+        }
+        for count in log_probabilities.values_mut() {
+            *count = log10(*count / total);
+        }
+        Ok(Self {
+            n,
+            log_probabilities,
+            floor: log10(0.01 / total),
+        })
+    }
+
+    /// The number of letters of the sequences of the model
+    pub fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl LanguageModel for NGramModel {
+    fn score(&self, text: &str) -> f64 {
+        let letters = normalize(text);
+        let boundaries = char_boundaries(&letters);
+        if boundaries.len() <= self.n {
+            return f64::INFINITY;
+        }
+
+        let log_probability: f64 = boundaries
+            .windows(self.n + 1)
+            .map(|window| {
+                let sequence = &letters[window[0]..window[self.n]];
+                self.log_probabilities
+                    .get(sequence)
+                    .copied()
+                    .unwrap_or(self.floor)
+            })
+            .sum();
+        -log_probability / (boundaries.len() - self.n) as f64
+    }
+}
+
+/// Words of a language
+///
+/// Texts are scored with the proportion of their letters that are not part of
+/// a word of the dictionary. As the whitespaces are removed, words are found even
+/// in texts that have none, like those of
+/// [AsciiLowerCaseAlphabet](crate::alphabets::AsciiLowerCaseAlphabet): from the
+/// start of the text, the longest word is taken each time one is found, and the
+/// next letter otherwise. Characters that are neither letters nor whitespaces
+/// are never part of a word.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::language::{DictionaryModel, LanguageModel};
+///
+/// let model = DictionaryModel::from_words(["ave", "imperator"]);
+/// assert_eq!(model.score("aveimperator"), 0.0);
+/// assert_eq!(model.score("Ave, Caesar"), 0.7);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DictionaryModel {
+    words: BTreeSet<String>,
+    /// The number of letters of the longest word
+    max_len: usize,
+}
+
+impl DictionaryModel {
+    /// Creates a dictionary with the given words, which are converted to lowercase
+    pub fn from_words<W: AsRef<str>>(words: impl IntoIterator<Item = W>) -> Self {
+        let words = words
+            .into_iter()
+            .map(|word| word.as_ref().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect::<BTreeSet<_>>();
+        let max_len = words
+            .iter()
+            .map(|word| word.chars().count())
+            .max()
+            .unwrap_or(0);
+        Self { words, max_len }
+    }
+
+    /// Loads the dictionary from a table of words, whose counts are not used
+    /// other than leaving out the words with a count of zero
+    pub fn from_table(table: &str) -> Result<Self, InvalidTable> {
+        let entries = parse_table(table)?;
+        Ok(Self::from_words(
+            entries
+                .into_iter()
+                .filter(|&(_, _, count)| count > 0.0)
+                .map(|(_, word, _)| word),
+        ))
+    }
+
+    /// The number of words of the dictionary
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl LanguageModel for DictionaryModel {
+    fn score(&self, text: &str) -> f64 {
+        let letters = normalize(text);
+        let boundaries = char_boundaries(&letters);
+        let letter_count = boundaries.len() - 1;
+        if letter_count == 0 {
+            return f64::INFINITY;
+        }
+
+        let mut covered = 0;
+        let mut start = 0;
+        while start < letter_count {
+            let longest_word = (1..=self.max_len.min(letter_count - start))
+                .rev()
+                .find(|len| {
+                    let word = &letters[boundaries[start]..boundaries[start + len]];
+                    self.words.contains(word)
+                });
+            match longest_word {
+                Some(len) => {
+                    covered += len;
+                    start += len;
+                }
+                None => start += 1,
+            }
+        }
+        1.0 - covered as f64 / letter_count as f64
+    }
+}
+
+/// The languages whose tables are embedded in the crate
+///
+/// The tables were computed from a few public domain texts of each language, about
+/// 14000 letters for English and 4000 to 5000 letters for the other languages. The
+/// English letter frequencies are the same as those of
+/// [english_chi_squared](crate::crack::english_chi_squared).
+///
+/// With so few letters, many quadgrams of a language are missing from its table:
+/// quadgram scores rank the decryptions of a text well, but tell languages apart
+/// less reliably than bigram or dictionary scores.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::language::{Language, LanguageModel};
+///
+/// let text = "Le petit chat dort sur le canapé pendant que la pluie tombe.";
+/// let language = Language::ALL
+///     .into_iter()
+///     .min_by(|a, b| a.dictionary().score(text).total_cmp(&b.dictionary().score(text)));
+/// assert_eq!(language, Some(Language::French));
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+    Latin,
+}
+
+/// The letters, bigrams, quadgrams and words tables of a language
+struct Tables {
+    letters: &'static str,
+    bigrams: &'static str,
+    quadgrams: &'static str,
+    words: &'static str,
+}
+
+macro_rules! tables {
+    ($language:literal) => {
+        Tables {
+            letters: include_str!(concat!("../data/", $language, "-letters.txt")),
+            bigrams: include_str!(concat!("../data/", $language, "-bigrams.txt")),
+            quadgrams: include_str!(concat!("../data/", $language, "-quadgrams.txt")),
+            words: include_str!(concat!("../data/", $language, "-words.txt")),
+        }
+    };
+}
+
+impl Language {
+    pub const ALL: [Language; 5] = [
+        Self::English,
+        Self::French,
+        Self::German,
+        Self::Spanish,
+        Self::Latin,
+    ];
+
+    fn tables(self) -> Tables {
+        match self {
+            Self::English => tables!("english"),
+            Self::French => tables!("french"),
+            Self::German => tables!("german"),
+            Self::Spanish => tables!("spanish"),
+            Self::Latin => tables!("latin"),
+        }
+    }
+
+    /// The frequencies of the letters of the language
+    pub fn letters(self) -> UnigramModel {
+        UnigramModel::from_table(self.tables().letters).expect("the embedded tables are valid")
+    }
+
+    /// The log-probabilities of the sequences of two letters of the language
+    pub fn bigrams(self) -> NGramModel {
+        NGramModel::from_table(self.tables().bigrams).expect("the embedded tables are valid")
+    }
+
+    /// The log-probabilities of the sequences of four letters of the language
+    pub fn quadgrams(self) -> NGramModel {
+        NGramModel::from_table(self.tables().quadgrams).expect("the embedded tables are valid")
+    }
+
+    /// Common words of the language
+    pub fn dictionary(self) -> DictionaryModel {
+        DictionaryModel::from_table(self.tables().words).expect("the embedded tables are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crack::english_chi_squared;
+
+    #[test]
+    fn invalid_tables() {
+        assert_eq!(
+            UnigramModel::from_table("a 1\nb").err(),
+            Some(InvalidTable::Syntax { line: 2 })
+        );
+        assert_eq!(
+            UnigramModel::from_table("a 1\n\nb -1").err(),
+            Some(InvalidTable::Syntax { line: 3 })
+        );
+        assert_eq!(
+            UnigramModel::from_table("# Only zeros\na 0").err(),
+            Some(InvalidTable::Empty)
+        );
+        assert_eq!(
+            DictionaryModel::from_table("").err(),
+            Some(InvalidTable::Empty)
+        );
+        assert_eq!(
+            NGramModel::from_table("ab 1\nabc 2").err(),
+            Some(InvalidTable::Length { line: 2 })
+        );
+    }
+
+    #[test]
+    fn log10_matches_std() {
+        for x in [
+            1e-300, 1e-9, 0.01, 0.5, 1.0, 1.5, 2.0, 10.0, 123456.789, 1e300,
+        ] {
+            assert!((log10(x) - x.log10()).abs() < 1e-12, "log10({x})");
+        }
+    }
+
+    #[test]
+    fn embedded_english_letters_match_chi_squared() {
+        let model = Language::English.letters();
+        for text in ["hello world", "khoor zruog", "Ave, Imperator!"] {
+            let difference = model.score(text) - english_chi_squared(text);
+            assert!(difference.abs() < 1e-3 * english_chi_squared(text));
+        }
+    }
+
+    #[test]
+    fn models_recognize_their_language() {
+        use crate::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+        use crate::crack::crack_with_model;
+        use crate::{CaesarEngine, ClearText, Shift};
+
+        // Sentences that are not in the texts the tables were computed from
+        let sentences = [
+            (
+                Language::English,
+                "the old man walked slowly along the quiet road towards the village",
+            ),
+            (
+                Language::French,
+                "le vieil homme marchait lentement sur la route vers le village",
+            ),
+            (
+                Language::German,
+                "der alte Mann ging langsam auf der stillen Strasse zum Dorf",
+            ),
+            (
+                Language::Spanish,
+                "el hombre viejo caminaba despacio por el camino hacia el pueblo",
+            ),
+            (
+                Language::Latin,
+                "senex lente per viam tacitam ad oppidum ambulabat",
+            ),
+        ];
+        for (language, sentence) in sentences {
+            let best = Language::ALL.into_iter().min_by(|a, b| {
+                a.bigrams()
+                    .score(sentence)
+                    .total_cmp(&b.bigrams().score(sentence))
+            });
+            assert_eq!(best, Some(language), "{sentence}");
+
+            let sentence = ClearText::<IncompleteAscii>::try_new(sentence).unwrap();
+            let encrypted = CaesarEngine::<IncompleteAscii>::new(Shift(11)).encrypt(&sentence);
+            let models: [&dyn LanguageModel; 2] = [&language.letters(), &language.dictionary()];
+            for model in models {
+                assert_eq!(crack_with_model(&encrypted, model)[0].shift, Shift(11));
+            }
+
+            let sentence = sentence.as_ref().replace(' ', "").to_lowercase();
+            let sentence = ClearText::<AsciiLowerCaseAlphabet>::try_new(sentence).unwrap();
+            let encrypted =
+                CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(11)).encrypt(&sentence);
+            let candidates = crack_with_model(&encrypted, &language.quadgrams());
+            assert_eq!(candidates[0].clear_text, sentence);
+        }
+    }
+}
@@ -6,14 +6,374 @@
 //! To summarize, in this encryption scheme, we have an alphabet in which the clear text is in.
 //! And to encrypt a clear text we shift the alphabet by a number.
 //!
+//! # Features
+//!
+//! - `std` (default): implements the [std::io] adapters of the [stream] module.
+//! - `alloc`: everything that needs an allocator, that is the texts and the engines.
+//!   Without it, only the [fixed] module is available, which works with buffers
+//!   provided by the caller.
+//! - `serde`: serialization of the texts and keys, see the `serialization` module.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 use crate::alphabets::Alphabet;
+#[cfg(feature = "alloc")]
+use crate::table::{CharTable, Substitution};
 
+#[cfg(feature = "alloc")]
+pub mod affine;
 pub mod alphabets;
+#[cfg(feature = "alloc")]
+pub mod crack;
+#[cfg(feature = "alloc")]
+pub mod cryptanalysis;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+pub mod fixed;
+#[cfg(feature = "alloc")]
+pub mod key;
+#[cfg(feature = "alloc")]
+pub mod language;
+#[cfg(feature = "alloc")]
+pub mod pipeline;
+#[cfg(feature = "alloc")]
+mod rng;
+#[cfg(feature = "serde")]
+pub mod serialization;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "alloc")]
+pub mod substitution;
+#[cfg(feature = "alloc")]
+mod table;
+#[cfg(feature = "alloc")]
+pub mod vigenere;
 
-pub struct Shift(pub usize);
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shift(pub isize);
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct CharacterNotInAlphabet(pub char);
+/// Shifts are numbers modulo the number of letters of the alphabet: a shift
+/// larger than the alphabet wraps around, and a negative shift is a shift to
+/// the left. The shift is reduced modulo the alphabet size when an engine is created.
+///
+/// `==` compares the numbers themselves. To compare, invert or add shifts modulo
+/// the size of an alphabet, [reduce](Shift::reduced) them to a [ReducedShift].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("abc").unwrap();
+///
+/// let left = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(-1));
+/// assert_eq!(&left.encrypt(&message), "zab");
+///
+/// let large = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(53));
+/// assert_eq!(&large.encrypt(&message), "bcd");
+///
+/// let twenty = Shift(20).reduced::<AsciiLowerCaseAlphabet>();
+/// let ten = Shift(10).reduced::<AsciiLowerCaseAlphabet>();
+/// assert_eq!(twenty + ten, Shift(4).reduced());
+/// assert_eq!(Shift(3).reduced::<AsciiLowerCaseAlphabet>().inverse(), Shift(-3).reduced());
+/// assert!(Shift(53).congruent::<AsciiLowerCaseAlphabet>(Shift(1)));
+/// # }
+/// ```
+impl Shift {
+    /// The shift undoing this one, or `None` for `Shift(isize::MIN)`, whose
+    /// opposite is not an `isize`: use [ReducedShift::inverse] for any shift.
+    pub fn inverse(self) -> Option<Self> {
+        self.0.checked_neg().map(Self)
+    }
+
+    /// Shifting by the sum is the same as shifting by `self` then by `rhs`,
+    /// `None` if the sum is out of the range of `isize`: use [ReducedShift]
+    /// to add any shifts.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    /// The shift as a number in `0..modulus`
+    pub fn reduce(self, modulus: usize) -> usize {
+        self.0.rem_euclid(modulus as isize) as usize
+    }
+
+    /// The equivalent shift in `0..A::letters().len()`
+    pub fn reduced<A: Alphabet>(self) -> ReducedShift<A> {
+        ReducedShift {
+            _marker: Default::default(),
+            shift: self.reduce(A::letters().len()),
+        }
+    }
+
+    /// Whether both shifts are the same in the alphabet `A`
+    pub fn congruent<A: Alphabet>(self, other: Self) -> bool {
+        self.reduced::<A>() == other.reduced::<A>()
+    }
+}
+
+/// A [Shift] reduced modulo the number of letters of the alphabet `A`
+///
+/// Reduced shifts are equal when they shift the letters of `A` the same way, and
+/// are inverted and added without overflow.
+pub struct ReducedShift<A> {
+    _marker: core::marker::PhantomData<A>,
+    /// The shift, in `0..A::letters().len()`
+    shift: usize,
+}
+
+impl<A: Alphabet> ReducedShift<A> {
+    /// The shift, in `0..A::letters().len()`
+    pub fn shift(self) -> Shift {
+        Shift(self.shift as isize)
+    }
+
+    /// The shift undoing this one
+    pub fn inverse(self) -> Self {
+        let alphabet_len = A::letters().len();
+        Self {
+            _marker: Default::default(),
+            shift: (alphabet_len - self.shift) % alphabet_len,
+        }
+    }
+}
+
+impl<A: Alphabet> core::ops::Add for ReducedShift<A> {
+    type Output = Self;
+
+    /// Shifting by `self + rhs` is the same as shifting by `self` then by `rhs`
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            _marker: Default::default(),
+            shift: (self.shift + rhs.shift) % A::letters().len(),
+        }
+    }
+}
+
+impl<A: Alphabet> From<ReducedShift<A>> for Shift {
+    fn from(shift: ReducedShift<A>) -> Self {
+        shift.shift()
+    }
+}
+
+impl<A> Clone for ReducedShift<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for ReducedShift<A> {}
+
+impl<A> PartialEq for ReducedShift<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.shift == other.shift
+    }
+}
+
+impl<A> Eq for ReducedShift<A> {}
+
+impl<A> core::fmt::Debug for ReducedShift<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ReducedShift").field(&self.shift).finish()
+    }
+}
+
+/// Error returned when a message contains a character that is not in the alphabet
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::{CharacterNotInAlphabet, ClearText};
+///
+/// let error = ClearText::<AsciiLowerCaseAlphabet>::try_new("déjà vu").unwrap_err();
+/// assert_eq!(error, CharacterNotInAlphabet::new('é', 1, 1));
+/// assert_eq!(error.to_string(), "character 'é' at index 1 is not in the alphabet");
+///
+/// // 'à' is the 4th character but starts at the 5th byte, as 'é' takes 2 bytes
+/// let errors = ClearText::<AsciiLowerCaseAlphabet>::try_new_collect_errors("déjà vu").unwrap_err();
+/// assert_eq!(
+///     errors,
+///     vec![
+///         CharacterNotInAlphabet::new('é', 1, 1),
+///         CharacterNotInAlphabet::new('à', 3, 4),
+///         CharacterNotInAlphabet::new(' ', 4, 6),
+///     ]
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CharacterNotInAlphabet {
+    pub character: char,
+    /// Position of the character in the message, counted in characters
+    pub index: usize,
+    /// Position of the first byte of the character in the UTF-8 encoded message
+    pub byte_offset: usize,
+}
+
+impl CharacterNotInAlphabet {
+    pub fn new(character: char, index: usize, byte_offset: usize) -> Self {
+        Self {
+            character,
+            index,
+            byte_offset,
+        }
+    }
+}
+
+impl core::fmt::Display for CharacterNotInAlphabet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "character {:?} at index {} is not in the alphabet",
+            self.character, self.index
+        )
+    }
+}
+
+impl core::error::Error for CharacterNotInAlphabet {}
+
+#[cfg(feature = "alloc")]
+/// Error returned when encrypting bytes with an alphabet that has
+/// characters that are not ascii, see [CaesarEngine::encrypt_bytes]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct NonAsciiAlphabet;
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for NonAsciiAlphabet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "the alphabet contains characters that are not ascii")
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for NonAsciiAlphabet {}
+
+#[cfg(feature = "alloc")]
+/// Finds all the characters of the message that are not in the alphabet, in one pass.
+fn foreign_characters<'a>(
+    alphabet_letters: &'a CharTable<()>,
+    message: &'a str,
+) -> impl Iterator<Item = CharacterNotInAlphabet> + 'a {
+    message
+        .char_indices()
+        .enumerate()
+        .filter(|(_, (_, c))| !alphabet_letters.contains(*c))
+        .map(|(index, (byte_offset, c))| CharacterNotInAlphabet::new(c, index, byte_offset))
+}
+
+/// What to do with the characters of a message that are not in the alphabet
+///
+/// The policy is given when creating a [ClearText] or a [CipherText]. Characters
+/// that are kept in the text by the policy are left unchanged by [CaesarEngine::encrypt]
+/// and [CaesarEngine::decrypt], so that only the letters of the alphabet are shifted.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::{CaesarEngine, ClearText, ForeignCharPolicy, Shift};
+///
+/// let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+///     "hello, world!",
+///     ForeignCharPolicy::Passthrough,
+/// )
+/// .unwrap();
+/// assert_eq!(&engine.encrypt(&message), "khoor, zruog!");
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+///     "hello, world!",
+///     ForeignCharPolicy::Drop,
+/// )
+/// .unwrap();
+/// assert_eq!(&engine.encrypt(&message), "khoorzruog");
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+///     "hello, world!",
+///     ForeignCharPolicy::Replace('_'),
+/// )
+/// .unwrap();
+/// assert_eq!(&engine.encrypt(&message), "khoor__zruog_");
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ForeignCharPolicy {
+    /// Characters not in the alphabet are an error
+    #[default]
+    Reject,
+    /// Characters not in the alphabet are kept unchanged
+    Passthrough,
+    /// Characters not in the alphabet are removed
+    Drop,
+    /// Characters not in the alphabet are replaced by the placeholder
+    ///
+    /// A placeholder that is a letter of the alphabet, like the `x` traditionally
+    /// used for nulls, is a letter of the text like the others: it is shifted by
+    /// the engines, and cannot be told apart from the letters of the message once
+    /// encrypted. Use a placeholder outside of the alphabet to keep it unchanged.
+    Replace(char),
+}
+
+impl ForeignCharPolicy {
+    /// The character put in place of a character that is not in the alphabet, while
+    /// encrypting or decrypting, if any
+    ///
+    /// The character is then encrypted or decrypted if it is a letter of the alphabet,
+    /// like a placeholder put in a text by [ClearText::try_new_with_policy].
+    fn replacement(
+        self,
+        foreign: CharacterNotInAlphabet,
+    ) -> Result<Option<char>, CharacterNotInAlphabet> {
+        match self {
+            Self::Reject => Err(foreign),
+            Self::Passthrough => Ok(Some(foreign.character)),
+            Self::Drop => Ok(None),
+            Self::Replace(placeholder) => Ok(Some(placeholder)),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ForeignCharPolicy {
+    /// Applies the policy to the characters of the message that are not in the alphabet.
+    fn apply(
+        self,
+        alphabet_letters: &'static [char],
+        message: String,
+    ) -> Result<String, CharacterNotInAlphabet> {
+        let alphabet_letters = CharTable::letters(alphabet_letters);
+        let is_foreign = |c: &char| !alphabet_letters.contains(*c);
+        match self {
+            Self::Reject => {
+                let first_error = foreign_characters(alphabet_letters, &message).next();
+                match first_error {
+                    Some(error) => Err(error),
+                    None => Ok(message),
+                }
+            }
+            Self::Passthrough => Ok(message),
+            Self::Drop => Ok(message.chars().filter(|c| !is_foreign(c)).collect()),
+            Self::Replace(placeholder) => Ok(message
+                .chars()
+                .map(|c| if is_foreign(&c) { placeholder } else { c })
+                .collect()),
+        }
+    }
+}
 
 /// Struct that encrypts and decrypts message.
 ///
@@ -53,54 +413,295 @@ pub struct CharacterNotInAlphabet(pub char);
 ///
 /// [encrypt]: Self::encrypt
 /// [decrypt]: Self::decrypt
+#[cfg(feature = "alloc")]
 pub struct CaesarEngine<A: Alphabet> {
-    _marker: std::marker::PhantomData<A>,
-    shifted_alphabet: Vec<char>,
+    _marker: core::marker::PhantomData<A>,
+    shift: Shift,
+    preserving_case: bool,
+    substitution: Substitution,
 }
 
+#[cfg(feature = "alloc")]
 impl<A: Alphabet> CaesarEngine<A> {
-    /// Creates a new
+    /// Creates a new engine for the given shift
+    ///
+    /// The encryption and decryption tables are computed once here, so that
+    /// encrypting or decrypting a letter does not depend on the alphabet size.
     pub fn new(shift: Shift) -> Self {
-        let mut shifted_alphabet = A::letters().to_vec();
-        shifted_alphabet.rotate_left(shift.0);
-
         Self {
             _marker: Default::default(),
-            shifted_alphabet,
+            shift,
+            preserving_case: false,
+            substitution: Substitution::shift(A::letters(), shift),
         }
     }
 
-    pub fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
-        let mut encrypted_message = String::with_capacity(clear_message.message.len());
-        let alphabet = A::letters();
-        for letter in clear_message.message.chars() {
-            let letter_index = alphabet.iter().position(|l| l == &letter).unwrap();
-            encrypted_message.push(self.shifted_alphabet[letter_index]);
+    /// Creates a new engine that shifts letters regardless of their case,
+    /// and keeps the case of each letter in the output
+    ///
+    /// The shift is done in the alphabet folded to lowercase, so with
+    /// [IncompleteAscii](alphabets::IncompleteAscii) `'z'` shifted by one is `','`
+    /// and not `'A'`. Uppercase letters that are not themselves in the alphabet,
+    /// like in [AsciiLowerCaseAlphabet](alphabets::AsciiLowerCaseAlphabet), can be
+    /// put in the text with [ForeignCharPolicy::Passthrough].
+    ///
+    /// When the alphabet has characters without case, an uppercase letter shifted
+    /// onto one of them loses its case: the encrypted text does not tell that it was
+    /// uppercase, and it is decrypted in lowercase. Decrypting then only gives the
+    /// message back for alphabets whose letters all have a case, or for messages
+    /// whose uppercase letters are not shifted onto such characters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+    /// use caesar_cipher::{CaesarEngine, ClearText, ForeignCharPolicy, Shift};
+    ///
+    /// let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new_preserving_case(Shift(3));
+    ///
+    /// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+    ///     "Hello, World!",
+    ///     ForeignCharPolicy::Passthrough,
+    /// )
+    /// .unwrap();
+    /// let encrypted_message = engine.encrypt(&message);
+    /// assert_eq!(&encrypted_message, "Khoor, Zruog!");
+    /// assert_eq!(engine.decrypt(&encrypted_message), message);
+    /// # }
+    /// ```
+    ///
+    /// The case of a letter shifted onto a symbol is lost:
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// use caesar_cipher::alphabets::IncompleteAscii;
+    /// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+    ///
+    /// let engine = CaesarEngine::<IncompleteAscii>::new_preserving_case(Shift(1));
+    ///
+    /// let message = ClearText::<IncompleteAscii>::try_new("Zebra").unwrap();
+    /// let encrypted_message = engine.encrypt(&message);
+    /// assert_eq!(&encrypted_message, ",fcsb");
+    /// assert_eq!(&engine.decrypt(&encrypted_message), "zebra");
+    /// # }
+    /// ```
+    pub fn new_preserving_case(shift: Shift) -> Self {
+        Self {
+            _marker: Default::default(),
+            shift,
+            preserving_case: true,
+            substitution: Substitution::shift_preserving_case(A::letters(), shift),
         }
+    }
+
+    /// The shift the engine was created with
+    pub fn shift(&self) -> Shift {
+        self.shift
+    }
+
+    /// Whether the engine was created with [CaesarEngine::new_preserving_case]
+    pub fn preserves_case(&self) -> bool {
+        self.preserving_case
+    }
 
+    /// Encrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
+    pub fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
         CipherText {
             _marker: Default::default(),
-            cipher: encrypted_message,
+            cipher: self
+                .substitution
+                .encrypt(&clear_message.message, ForeignCharPolicy::Passthrough)
+                .expect("passing characters through cannot fail"),
         }
     }
 
+    /// Decrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
     pub fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
-        let mut clear_message = String::with_capacity(cipher_message.cipher.len());
-        let alphabet = A::letters();
-        for letter in cipher_message.cipher.chars() {
-            let letter_index = self
-                .shifted_alphabet
-                .iter()
-                .position(|l| &letter == l)
-                .unwrap();
-            clear_message.push(alphabet[letter_index]);
+        ClearText {
+            _marker: Default::default(),
+            message: self
+                .substitution
+                .decrypt(&cipher_message.cipher, ForeignCharPolicy::Passthrough)
+                .expect("passing characters through cannot fail"),
         }
+    }
+
+    /// Encrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_encrypt(
+        &self,
+        clear_message: &ClearText<A>,
+    ) -> Result<CipherText<A>, CharacterNotInAlphabet> {
+        Ok(CipherText {
+            _marker: Default::default(),
+            cipher: self
+                .substitution
+                .encrypt(&clear_message.message, ForeignCharPolicy::Reject)?,
+        })
+    }
+
+    /// Decrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_decrypt(
+        &self,
+        cipher_message: &CipherText<A>,
+    ) -> Result<ClearText<A>, CharacterNotInAlphabet> {
+        Ok(ClearText {
+            _marker: Default::default(),
+            message: self
+                .substitution
+                .decrypt(&cipher_message.cipher, ForeignCharPolicy::Reject)?,
+        })
+    }
+
+    /// Encrypts the message into `output`, replacing its content
+    ///
+    /// The allocation of `output` is reused, so encrypting many messages with
+    /// the same buffer only allocates when a message is longer than all the previous ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+    /// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+    ///
+    /// let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+    ///
+    /// let mut output = String::new();
+    /// for message in ["hello", "world"] {
+    ///     let message = ClearText::<AsciiLowerCaseAlphabet>::try_new(message).unwrap();
+    ///     engine.encrypt_into(&message, &mut output);
+    ///     assert_eq!(output, engine.encrypt(&message).as_ref().as_str());
+    /// }
+    /// # }
+    /// ```
+    pub fn encrypt_into(&self, clear_message: &ClearText<A>, output: &mut String) {
+        output.clear();
+        self.substitution
+            .encrypt_into(&clear_message.message, output);
+    }
+
+    /// Decrypts the message into `output`, replacing its content
+    ///
+    /// The allocation of `output` is reused, see [CaesarEngine::encrypt_into].
+    pub fn decrypt_into(&self, cipher_message: &CipherText<A>, output: &mut String) {
+        output.clear();
+        self.substitution
+            .decrypt_into(&cipher_message.cipher, output);
+    }
 
+    /// Encrypts the message, reusing its buffer for the cipher text
+    ///
+    /// The letters are replaced in place when each letter of the alphabet is encrypted
+    /// to a letter with the same UTF-8 length, which is always the case with ascii
+    /// alphabets. Otherwise a new buffer is allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// use caesar_cipher::alphabets::GreekAlphabet;
+    /// use caesar_cipher::{CaesarEngine, ClearText, Shift};
+    ///
+    /// let engine = CaesarEngine::<GreekAlphabet>::new(Shift(1));
+    /// let message = ClearText::<GreekAlphabet>::try_new("καισαρ").unwrap();
+    ///
+    /// let encrypted_message = engine.encrypt_in_place(message);
+    /// assert_eq!(&encrypted_message, "λβκτβς");
+    /// assert_eq!(&engine.decrypt_in_place(encrypted_message), "καισαρ");
+    /// # }
+    /// ```
+    pub fn encrypt_in_place(&self, clear_message: ClearText<A>) -> CipherText<A> {
+        CipherText {
+            _marker: Default::default(),
+            cipher: self.substitution.encrypt_in_place(clear_message.message),
+        }
+    }
+
+    /// Decrypts the message, reusing its buffer for the clear text
+    ///
+    /// See [CaesarEngine::encrypt_in_place].
+    pub fn decrypt_in_place(&self, cipher_message: CipherText<A>) -> ClearText<A> {
         ClearText {
             _marker: Default::default(),
-            message: clear_message,
+            message: self.substitution.decrypt_in_place(cipher_message.cipher),
         }
     }
+
+    /// Encrypts the letters of the bytes in place, for alphabets made of ascii characters
+    ///
+    /// The bytes do not need to be validated as UTF-8 first: bytes that are not a
+    /// letter of the alphabet are left unchanged, so valid UTF-8 stays valid.
+    ///
+    /// Returns [NonAsciiAlphabet], without changing the bytes, if the alphabet
+    /// contains a character that is not ascii.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// use caesar_cipher::alphabets::{AsciiLowerCaseAlphabet, GreekAlphabet};
+    /// use caesar_cipher::{CaesarEngine, NonAsciiAlphabet, Shift};
+    ///
+    /// let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+    ///
+    /// let mut bytes = *b"hello, world!";
+    /// engine.encrypt_bytes(&mut bytes).unwrap();
+    /// assert_eq!(&bytes, b"khoor, zruog!");
+    /// engine.decrypt_bytes(&mut bytes).unwrap();
+    /// assert_eq!(&bytes, b"hello, world!");
+    ///
+    /// let engine = CaesarEngine::<GreekAlphabet>::new(Shift(3));
+    /// assert_eq!(engine.encrypt_bytes(&mut bytes), Err(NonAsciiAlphabet));
+    /// # }
+    /// ```
+    pub fn encrypt_bytes(&self, bytes: &mut [u8]) -> Result<(), NonAsciiAlphabet> {
+        self.substitution
+            .encrypt_bytes(bytes)
+            .then_some(())
+            .ok_or(NonAsciiAlphabet)
+    }
+
+    /// Decrypts the letters of the bytes in place, for alphabets made of ascii characters
+    ///
+    /// See [CaesarEngine::encrypt_bytes].
+    pub fn decrypt_bytes(&self, bytes: &mut [u8]) -> Result<(), NonAsciiAlphabet> {
+        self.substitution
+            .decrypt_bytes(bytes)
+            .then_some(())
+            .ok_or(NonAsciiAlphabet)
+    }
+}
+
+/// A cipher tied to an [Alphabet], abstracting over the engines of this crate
+///
+/// Ciphers can be chained with a [Pipeline](pipeline::Pipeline).
+#[cfg(feature = "alloc")]
+pub trait Cipher<A: Alphabet> {
+    /// Encrypts the message
+    fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A>;
+
+    /// Decrypts the message
+    fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A>;
+}
+
+#[cfg(feature = "alloc")]
+impl<A: Alphabet> Cipher<A> for CaesarEngine<A> {
+    fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        CaesarEngine::encrypt(self, clear_message)
+    }
+
+    fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        CaesarEngine::decrypt(self, cipher_message)
+    }
 }
 
 /// A Clear text is a non encrypted message
@@ -124,58 +725,147 @@ impl<A: Alphabet> CaesarEngine<A> {
 /// use caesar_cipher::{CharacterNotInAlphabet, ClearText};
 /// let clear_text = ClearText::<AsciiLowerCaseAlphabet>::try_new("mEssage");
 /// assert!(clear_text.is_err());
-/// assert_eq!(clear_text, Err(CharacterNotInAlphabet('E')));
+/// assert_eq!(clear_text, Err(CharacterNotInAlphabet::new('E', 1, 1)));
 /// ```
 ///
+#[cfg(feature = "alloc")]
 #[derive(Debug, Eq, PartialEq)]
 pub struct ClearText<A> {
-    _marker: std::marker::PhantomData<A>,
+    _marker: core::marker::PhantomData<A>,
     message: String,
 }
 
+#[cfg(feature = "alloc")]
 impl<A: Alphabet> ClearText<A> {
     pub fn try_new<T: ToString>(message: T) -> Result<Self, CharacterNotInAlphabet> {
+        Self::try_new_with_policy(message, ForeignCharPolicy::Reject)
+    }
+
+    /// Creates a clear text, handling the characters that are not in the alphabet
+    /// according to the `policy`
+    pub fn try_new_with_policy<T: ToString>(
+        message: T,
+        policy: ForeignCharPolicy,
+    ) -> Result<Self, CharacterNotInAlphabet> {
+        Ok(Self {
+            _marker: Default::default(),
+            message: policy.apply(A::letters(), message.to_string())?,
+        })
+    }
+
+    /// Same as [ClearText::try_new], but returns every character that is not
+    /// in the alphabet instead of only the first one
+    pub fn try_new_collect_errors<T: ToString>(
+        message: T,
+    ) -> Result<Self, Vec<CharacterNotInAlphabet>> {
         let message = message.to_string();
-        let alphabet_letters = A::letters();
-        let pos = message
-            .chars()
-            .position(|ref c| !alphabet_letters.contains(c));
-        if let Some(p) = pos {
-            Err(CharacterNotInAlphabet(message.chars().nth(p).unwrap()))
-        } else {
+        let errors =
+            foreign_characters(CharTable::letters(A::letters()), &message).collect::<Vec<_>>();
+        if errors.is_empty() {
             Ok(Self {
                 _marker: Default::default(),
                 message,
             })
+        } else {
+            Err(errors)
         }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<A> AsRef<String> for ClearText<A> {
     fn as_ref(&self) -> &String {
         &self.message
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<A: Alphabet> PartialEq<str> for ClearText<A> {
     fn eq(&self, other: &str) -> bool {
-        &self.message == other
+        self.message == other
     }
 }
 
+/// A Cipher text is an encrypted message
+///
+/// Cipher texts are usually produced by [CaesarEngine::encrypt], but
+/// they can also be built from an untrusted string (e.g. received over the wire)
+/// with [CipherText::try_new], which performs the same validation as [ClearText::try_new].
+///
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::{CaesarEngine, CharacterNotInAlphabet, CipherText, Shift};
+///
+/// let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+///
+/// let cipher_text = CipherText::<AsciiLowerCaseAlphabet>::try_new("khoor").unwrap();
+/// assert_eq!(&engine.decrypt(&cipher_text), "hello");
+///
+/// let cipher_text = CipherText::<AsciiLowerCaseAlphabet>::try_new("khoor!");
+/// assert_eq!(cipher_text, Err(CharacterNotInAlphabet::new('!', 5, 5)));
+/// ```
+#[cfg(feature = "alloc")]
 #[derive(Debug, Eq, PartialEq)]
 pub struct CipherText<A: Alphabet> {
-    _marker: std::marker::PhantomData<A>,
+    _marker: core::marker::PhantomData<A>,
     cipher: String,
 }
 
+#[cfg(feature = "alloc")]
+impl<A: Alphabet> CipherText<A> {
+    pub fn try_new<T: ToString>(cipher: T) -> Result<Self, CharacterNotInAlphabet> {
+        Self::try_new_with_policy(cipher, ForeignCharPolicy::Reject)
+    }
+
+    /// Creates a cipher text, handling the characters that are not in the alphabet
+    /// according to the `policy`
+    pub fn try_new_with_policy<T: ToString>(
+        cipher: T,
+        policy: ForeignCharPolicy,
+    ) -> Result<Self, CharacterNotInAlphabet> {
+        Ok(Self {
+            _marker: Default::default(),
+            cipher: policy.apply(A::letters(), cipher.to_string())?,
+        })
+    }
+
+    /// Same as [CipherText::try_new], but returns every character that is not
+    /// in the alphabet instead of only the first one
+    pub fn try_new_collect_errors<T: ToString>(
+        cipher: T,
+    ) -> Result<Self, Vec<CharacterNotInAlphabet>> {
+        let cipher = cipher.to_string();
+        let errors =
+            foreign_characters(CharTable::letters(A::letters()), &cipher).collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(Self {
+                _marker: Default::default(),
+                cipher,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<A: Alphabet> AsRef<String> for CipherText<A> {
+    fn as_ref(&self) -> &String {
+        &self.cipher
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl<A: Alphabet> PartialEq<str> for CipherText<A> {
     fn eq(&self, other: &str) -> bool {
-        &self.cipher == other
+        self.cipher == other
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
     use crate::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
@@ -207,7 +897,143 @@ mod tests {
     #[test]
     fn test_letter_not_in_alphabet() {
         let result = ClearText::<AsciiLowerCaseAlphabet>::try_new(String::from("hello world"));
-        assert_eq!(result, Err(CharacterNotInAlphabet(' ')));
+        assert_eq!(result, Err(CharacterNotInAlphabet::new(' ', 5, 5)));
+    }
+
+    #[test]
+    fn cipher_text_from_untrusted_string() {
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+
+        let cipher = CipherText::<AsciiLowerCaseAlphabet>::try_new("khoorzruog").unwrap();
+        let message = engine.try_decrypt(&cipher).unwrap();
+        assert_eq!(&message, "helloworld");
+
+        let result = CipherText::<AsciiLowerCaseAlphabet>::try_new("khoor zruog");
+        assert_eq!(result, Err(CharacterNotInAlphabet::new(' ', 5, 5)));
+    }
+
+    #[test]
+    fn passthrough_round_trip() {
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+            "Hello world!",
+            ForeignCharPolicy::Passthrough,
+        )
+        .unwrap();
+
+        let encrypted_message = engine.encrypt(&message);
+        assert_eq!(&encrypted_message, "Hhoor zruog!");
+        assert_eq!(
+            engine.try_encrypt(&message),
+            Err(CharacterNotInAlphabet::new('H', 0, 0))
+        );
+
+        let decrypted_message = engine.decrypt(&encrypted_message);
+        assert_eq!(decrypted_message, message);
+    }
+
+    #[test]
+    fn placeholder_in_alphabet_is_encrypted() {
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+            "a b",
+            ForeignCharPolicy::Replace('x'),
+        )
+        .unwrap();
+        assert_eq!(&message, "axb");
+        assert_eq!(&engine.encrypt(&message), "dae");
+
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+            "a b",
+            ForeignCharPolicy::Replace('_'),
+        )
+        .unwrap();
+        assert_eq!(&engine.encrypt(&message), "d_e");
+    }
+
+    #[test]
+    fn preserving_case_shifts_in_lowercase_alphabet() {
+        let engine = CaesarEngine::<IncompleteAscii>::new_preserving_case(Shift(3));
+        let message = ClearText::<IncompleteAscii>::try_new("Ave Imperator").unwrap();
+
+        let encrypted_message = engine.encrypt(&message);
+        assert_eq!(&encrypted_message, "Dyh'Lpshudwru");
+
+        let decrypted_message = engine.decrypt(&encrypted_message);
+        assert_eq!(decrypted_message, message);
+    }
+
+    #[test]
+    fn buffer_reusing_apis_match_encrypt() {
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+            "Déjà vu, encore",
+            ForeignCharPolicy::Passthrough,
+        )
+        .unwrap();
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new_preserving_case(Shift(5));
+        let encrypted_message = engine.encrypt(&message);
+
+        let mut output = String::from("a previous message, longer than the next one");
+        engine.encrypt_into(&message, &mut output);
+        assert_eq!(&output, encrypted_message.as_ref());
+
+        let mut bytes = message.as_ref().clone().into_bytes();
+        engine.encrypt_bytes(&mut bytes).unwrap();
+        assert_eq!(bytes, encrypted_message.as_ref().as_bytes());
+
+        let in_place = engine.encrypt_in_place(message);
+        assert_eq!(in_place, encrypted_message);
+        engine.decrypt_into(&in_place, &mut output);
+        assert_eq!(&engine.decrypt_in_place(in_place), output.as_str());
+    }
+
+    #[test]
+    fn shifts_are_modular() {
+        let message = ClearText::<IncompleteAscii>::try_new("Ave Imperator").unwrap();
+        let reference = CaesarEngine::<IncompleteAscii>::new(Shift(17)).encrypt(&message);
+
+        for shift in [
+            Shift(77),
+            Shift(-43),
+            Shift(17 + 60 * 1000),
+            Shift(8).checked_add(Shift(9)).unwrap(),
+            (Shift(8).reduced::<IncompleteAscii>() + Shift(9).reduced()).shift(),
+        ] {
+            assert!(shift.congruent::<IncompleteAscii>(Shift(17)));
+            let engine = CaesarEngine::<IncompleteAscii>::new(shift);
+            assert_eq!(engine.encrypt(&message), reference);
+        }
+
+        let inverse_engine = CaesarEngine::<IncompleteAscii>::new(Shift(17).inverse().unwrap());
+        let cipher = CipherText::<IncompleteAscii>::try_new(reference.as_ref()).unwrap();
+        let clear = ClearText::<IncompleteAscii>::try_new(cipher.as_ref()).unwrap();
+        assert_eq!(&inverse_engine.encrypt(&clear), "Ave Imperator");
+        assert_eq!(Shift(-43).reduced::<IncompleteAscii>(), Shift(17).reduced());
+        assert_ne!(Shift(-43).reduced::<IncompleteAscii>(), Shift(16).reduced());
+    }
+
+    #[test]
+    fn shifts_at_the_edges_of_isize() {
+        let message = ClearText::<IncompleteAscii>::try_new("Ave").unwrap();
+
+        assert_eq!(Shift(isize::MIN).inverse(), None);
+        assert_eq!(Shift(isize::MAX).checked_add(Shift(1)), None);
+
+        for shift in [Shift(isize::MIN), Shift(isize::MAX)] {
+            let engine = CaesarEngine::<IncompleteAscii>::new(shift);
+            let inverse = shift.reduced::<IncompleteAscii>().inverse();
+            let inverse_engine = CaesarEngine::<IncompleteAscii>::new(inverse.shift());
+            let cipher = engine.encrypt(&message);
+            let clear = ClearText::<IncompleteAscii>::try_new(cipher.as_ref()).unwrap();
+            assert_eq!(&inverse_engine.encrypt(&clear), "Ave");
+        }
+
+        // isize::MAX + 1 is 2^63, and 2^63 = 8 modulo 60
+        let sum = Shift(isize::MAX).reduced::<IncompleteAscii>() + Shift(1).reduced();
+        assert_eq!(sum, Shift(8).reduced());
+        // isize::MIN + isize::MIN is -2^64, and -2^64 = -16 modulo 60
+        let sum = Shift(isize::MIN).reduced::<IncompleteAscii>() + Shift(isize::MIN).reduced();
+        assert_eq!(sum, Shift(-16).reduced());
     }
 
     #[test]
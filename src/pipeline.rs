@@ -0,0 +1,235 @@
+//! Chaining of several ciphers
+//!
+//! A [Pipeline] encrypts a message with each of its ciphers in turn, and decrypts it
+//! by applying the ciphers in reverse order. Consecutive Caesar shifts are the same
+//! as a single shift by their sum, so they are collapsed into a single [CaesarEngine]
+//! when they are added to the pipeline, unless they preserve case.
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::alphabets::Alphabet;
+use crate::{CaesarEngine, Cipher, CipherText, ClearText};
+
+enum Stage<A: Alphabet> {
+    Caesar(CaesarEngine<A>),
+    Other(Box<dyn Cipher<A>>),
+}
+
+impl<A: Alphabet> Stage<A> {
+    fn cipher(&self) -> &dyn Cipher<A> {
+        match self {
+            Self::Caesar(engine) => engine,
+            Self::Other(cipher) => cipher.as_ref(),
+        }
+    }
+}
+
+/// Ciphers applied one after the other
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::pipeline::Pipeline;
+/// use caesar_cipher::substitution::SubstitutionEngine;
+/// use caesar_cipher::{CaesarEngine, Cipher, ClearText, Shift};
+///
+/// type Engine = CaesarEngine<AsciiLowerCaseAlphabet>;
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("fleeatonce").unwrap();
+///
+/// let pipeline = Pipeline::new()
+///     .then(Engine::new(Shift(3)))
+///     .then(Engine::new(Shift(-1)))
+///     .then(SubstitutionEngine::atbash());
+/// // The two shifts were collapsed into a single one
+/// assert_eq!(pipeline.len(), 2);
+///
+/// let encrypted_message = pipeline.encrypt(&message);
+/// assert_eq!(&encrypted_message, "smttxejkvt");
+/// assert_eq!(pipeline.decrypt(&encrypted_message), message);
+/// ```
+pub struct Pipeline<A: Alphabet> {
+    stages: Vec<Stage<A>>,
+}
+
+impl<A: Alphabet + 'static> Pipeline<A> {
+    /// Creates an empty pipeline, which leaves messages unchanged
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// Adds a cipher at the end of the pipeline
+    ///
+    /// A [CaesarEngine] following another one is merged with it, unless one of them
+    /// [preserves case](CaesarEngine::preserves_case): a letter shifted onto a
+    /// character without case loses its case, so two shifts preserving case are not
+    /// always the same as a single one.
+    pub fn then<C: Cipher<A> + 'static>(mut self, cipher: C) -> Self {
+        let cipher: Box<dyn Any> = Box::new(cipher);
+        match cipher.downcast::<CaesarEngine<A>>() {
+            Ok(engine) => {
+                let stage = match self.stages.pop() {
+                    Some(Stage::Caesar(last))
+                        if !last.preserves_case() && !engine.preserves_case() =>
+                    {
+                        CaesarEngine::new(
+                            (last.shift().reduced::<A>() + engine.shift().reduced()).shift(),
+                        )
+                    }
+                    last => {
+                        self.stages.extend(last);
+                        *engine
+                    }
+                };
+                self.stages.push(Stage::Caesar(stage));
+            }
+            Err(cipher) => {
+                let cipher = *cipher.downcast::<C>().expect("the cipher has type C");
+                self.stages.push(Stage::Other(Box::new(cipher)));
+            }
+        }
+        self
+    }
+
+    /// The number of ciphers of the pipeline, after collapsing the Caesar shifts
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl<A: Alphabet + 'static> Default for Pipeline<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Alphabet> Cipher<A> for Pipeline<A> {
+    fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        let mut message = clear_message.message.clone();
+        for stage in &self.stages {
+            let clear_message = ClearText {
+                _marker: Default::default(),
+                message,
+            };
+            message = stage.cipher().encrypt(&clear_message).cipher;
+        }
+        CipherText {
+            _marker: Default::default(),
+            cipher: message,
+        }
+    }
+
+    fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        let mut cipher = cipher_message.cipher.clone();
+        for stage in self.stages.iter().rev() {
+            let cipher_message = CipherText {
+                _marker: Default::default(),
+                cipher,
+            };
+            cipher = stage.cipher().decrypt(&cipher_message).message;
+        }
+        ClearText {
+            _marker: Default::default(),
+            message: cipher,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::IncompleteAscii;
+    use crate::vigenere::VigenereEngine;
+    use crate::Shift;
+
+    type Engine = CaesarEngine<IncompleteAscii>;
+
+    #[test]
+    fn caesar_shifts_are_collapsed() {
+        let message = ClearText::<IncompleteAscii>::try_new("Ave Imperator").unwrap();
+
+        let pipeline = Pipeline::new()
+            .then(Engine::new(Shift(17)))
+            .then(Engine::new(Shift(50)))
+            .then(Engine::new(Shift(-7)));
+        assert_eq!(pipeline.len(), 1);
+        assert_eq!(
+            pipeline.encrypt(&message),
+            Engine::new(Shift(0)).encrypt(&message)
+        );
+
+        // A shift preserving case is not done in the same alphabet as the others
+        let pipeline = Pipeline::new()
+            .then(Engine::new(Shift(3)))
+            .then(Engine::new_preserving_case(Shift(3)));
+        assert_eq!(pipeline.len(), 2);
+        let encrypted_message = pipeline.encrypt(&message);
+        assert_eq!(pipeline.decrypt(&encrypted_message), message);
+
+        let pipeline = Pipeline::new()
+            .then(Engine::new(Shift(isize::MAX)))
+            .then(Engine::new(Shift(1)));
+        assert_eq!(pipeline.len(), 1);
+        assert_eq!(
+            pipeline.encrypt(&message),
+            Engine::new(Shift(isize::MAX % 60 + 1)).encrypt(&message)
+        );
+    }
+
+    #[test]
+    fn same_output_as_engines_in_sequence() {
+        let message = ClearText::<IncompleteAscii>::try_new("Zebra").unwrap();
+        for preserving_case in [false, true] {
+            let engine = |shift| {
+                if preserving_case {
+                    Engine::new_preserving_case(Shift(shift))
+                } else {
+                    Engine::new(Shift(shift))
+                }
+            };
+            let pipeline = Pipeline::new().then(engine(1)).then(engine(10));
+
+            let expected = engine(1).encrypt(&message);
+            let expected = ClearText::<IncompleteAscii>::try_new(expected.as_ref()).unwrap();
+            let expected = engine(10).encrypt(&expected);
+            assert_eq!(pipeline.encrypt(&message), expected);
+
+            let decrypted = engine(10).decrypt(&expected);
+            let decrypted = CipherText::<IncompleteAscii>::try_new(decrypted.as_ref()).unwrap();
+            assert_eq!(pipeline.decrypt(&expected), engine(1).decrypt(&decrypted));
+        }
+    }
+
+    #[test]
+    fn decryption_is_in_reverse_order() {
+        let message =
+            ClearText::<IncompleteAscii>::try_new("Ave Imperator, morituri te salutant").unwrap();
+        let vigenere = || VigenereEngine::<IncompleteAscii>::try_new("Caesar").unwrap();
+
+        let pipeline = Pipeline::new()
+            .then(vigenere())
+            .then(Engine::new(Shift(5)))
+            .then(vigenere());
+        assert_eq!(pipeline.len(), 3);
+
+        let expected = vigenere().encrypt(&message);
+        let expected = ClearText::<IncompleteAscii>::try_new(expected.as_ref()).unwrap();
+        let expected = Engine::new(Shift(5)).encrypt(&expected);
+        let expected = ClearText::<IncompleteAscii>::try_new(expected.as_ref()).unwrap();
+        let expected = vigenere().encrypt(&expected);
+
+        let encrypted_message = pipeline.encrypt(&message);
+        assert_eq!(encrypted_message, expected);
+        assert_eq!(pipeline.decrypt(&encrypted_message), message);
+
+        let empty = Pipeline::<IncompleteAscii>::default();
+        assert!(empty.is_empty());
+        assert_eq!(&empty.encrypt(&message), message.as_ref().as_str());
+    }
+}
@@ -0,0 +1,300 @@
+//! [Serde](https://serde.rs) support, enabled by the `serde` feature
+//!
+//! [ClearText] and [CipherText] are serialized as plain strings. When they are
+//! deserialized, they are validated against the letters of their [Alphabet] just
+//! like [ClearText::try_new] and [CipherText::try_new] do, so characters that are
+//! not in the alphabet are rejected.
+//!
+//! Wrapping a text in [Tagged] also records the [name](Alphabet::name) of its alphabet,
+//! so that loading a text with a different alphabet than the one it was saved with
+//! is detected, even if all its characters happen to be in both alphabets.
+//!
+//! Texts created with [ForeignCharPolicy::Passthrough] or with a
+//! [ForeignCharPolicy::Replace] placeholder that is not in the alphabet keep
+//! characters that are not in the alphabet. They cannot be loaded back from the
+//! plain string form, which only accepts letters of the alphabet, but [Tagged]
+//! records that the text has such characters and loads them back with
+//! [ForeignCharPolicy::Passthrough].
+//!
+//! # Examples
+//!
+//! ```
+//! use caesar_cipher::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+//! use caesar_cipher::serialization::Tagged;
+//! use caesar_cipher::{ClearText, ForeignCharPolicy};
+//!
+//! let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("hello").unwrap();
+//!
+//! let json = serde_json::to_string(&message).unwrap();
+//! assert_eq!(json, r#""hello""#);
+//! let loaded: ClearText<IncompleteAscii> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(&loaded, "hello");
+//!
+//! let json = serde_json::to_string(&Tagged(message)).unwrap();
+//! assert_eq!(json, r#"{"alphabet":"ascii-lowercase","text":"hello"}"#);
+//! let loaded = serde_json::from_str::<Tagged<ClearText<IncompleteAscii>>>(&json);
+//! assert!(loaded.is_err());
+//!
+//! let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+//!     "hello, world",
+//!     ForeignCharPolicy::Passthrough,
+//! )
+//! .unwrap();
+//! let json = serde_json::to_string(&message).unwrap();
+//! assert!(serde_json::from_str::<ClearText<AsciiLowerCaseAlphabet>>(&json).is_err());
+//!
+//! let json = serde_json::to_string(&Tagged(message)).unwrap();
+//! assert_eq!(
+//!     json,
+//!     r#"{"alphabet":"ascii-lowercase","text":"hello, world","policy":"passthrough"}"#
+//! );
+//! let Tagged(loaded) =
+//!     serde_json::from_str::<Tagged<ClearText<AsciiLowerCaseAlphabet>>>(&json).unwrap();
+//! assert_eq!(&loaded, "hello, world");
+//! ```
+//!
+//! A [Key] is always serialized with the name of its alphabet, and with the
+//! cipher and the key written as in the last line of [key files](crate::key).
+//! Deserializing it checks the name of the alphabet, like reading a key file does.
+//!
+//! ```
+//! use caesar_cipher::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+//! use caesar_cipher::key::Key;
+//! use caesar_cipher::vigenere::VigenereEngine;
+//!
+//! let key = Key::from(VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("lemon").unwrap());
+//!
+//! let json = serde_json::to_string(&key).unwrap();
+//! assert_eq!(json, r#"{"alphabet":"ascii-lowercase","key":"vigenere \"lemon\""}"#);
+//! let loaded: Key<AsciiLowerCaseAlphabet> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(loaded.to_string(), key.to_string());
+//! assert!(serde_json::from_str::<Key<IncompleteAscii>>(&json).is_err());
+//! ```
+use alloc::string::String;
+
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::alphabets::Alphabet;
+use crate::key::Key;
+use crate::table::CharTable;
+use crate::{CharacterNotInAlphabet, CipherText, ClearText, ForeignCharPolicy};
+
+/// A text serialized along with the name of its alphabet
+#[derive(Debug, Eq, PartialEq)]
+pub struct Tagged<T>(pub T);
+
+#[derive(Deserialize)]
+struct TaggedText {
+    alphabet: String,
+    text: String,
+    #[serde(default)]
+    policy: Option<StoredPolicy>,
+}
+
+/// How the characters that are not in the alphabet are loaded back,
+/// only recorded when the text has such characters
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StoredPolicy {
+    Passthrough,
+}
+
+impl StoredPolicy {
+    fn of<A: Alphabet>(text: &str) -> Option<Self> {
+        let letters = CharTable::letters(A::letters());
+        text.chars()
+            .any(|c| !letters.contains(c))
+            .then_some(Self::Passthrough)
+    }
+}
+
+impl From<Option<StoredPolicy>> for ForeignCharPolicy {
+    fn from(policy: Option<StoredPolicy>) -> Self {
+        match policy {
+            None => Self::Reject,
+            Some(StoredPolicy::Passthrough) => Self::Passthrough,
+        }
+    }
+}
+
+fn not_in_alphabet<E: Error>(error: CharacterNotInAlphabet) -> E {
+    E::custom(error)
+}
+
+#[derive(Deserialize)]
+struct StoredKey {
+    alphabet: String,
+    key: String,
+}
+
+impl<A: Alphabet> Serialize for Key<A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Key", 2)?;
+        state.serialize_field("alphabet", A::name())?;
+        state.serialize_field("key", &self.key_line())?;
+        state.end()
+    }
+}
+
+impl<'de, A: Alphabet> Deserialize<'de> for Key<A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = StoredKey::deserialize(deserializer)?;
+        if stored.alphabet != A::name() {
+            return Err(D::Error::custom(format_args!(
+                "expected a key for the alphabet `{}`, found `{}`",
+                A::name(),
+                stored.alphabet
+            )));
+        }
+        Key::parse_key_line(&stored.key, 1).map_err(|error| {
+            D::Error::custom(format_args!("invalid key `{}`: {error:?}", stored.key))
+        })
+    }
+}
+
+macro_rules! impl_serde_for_text {
+    ($text_type:ident, $field:ident) => {
+        impl<A: Alphabet> Serialize for $text_type<A> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.$field)
+            }
+        }
+
+        impl<'de, A: Alphabet> Deserialize<'de> for $text_type<A> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let text = String::deserialize(deserializer)?;
+                Self::try_new(text).map_err(not_in_alphabet)
+            }
+        }
+
+        impl<A: Alphabet> Serialize for Tagged<$text_type<A>> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let policy = StoredPolicy::of::<A>(&self.0.$field);
+                let len = if policy.is_some() { 3 } else { 2 };
+                let mut state = serializer.serialize_struct(stringify!($text_type), len)?;
+                state.serialize_field("alphabet", A::name())?;
+                state.serialize_field("text", &self.0.$field)?;
+                if let Some(policy) = policy {
+                    state.serialize_field("policy", &policy)?;
+                }
+                state.end()
+            }
+        }
+
+        impl<'de, A: Alphabet> Deserialize<'de> for Tagged<$text_type<A>> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let tagged = TaggedText::deserialize(deserializer)?;
+                if tagged.alphabet != A::name() {
+                    return Err(D::Error::custom(format_args!(
+                        "expected a text in the alphabet `{}`, found `{}`",
+                        A::name(),
+                        tagged.alphabet
+                    )));
+                }
+                $text_type::try_new_with_policy(tagged.text, tagged.policy.into())
+                    .map(Tagged)
+                    .map_err(not_in_alphabet)
+            }
+        }
+    };
+}
+
+impl_serde_for_text!(ClearText, message);
+impl_serde_for_text!(CipherText, cipher);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::affine::AffineEngine;
+    use crate::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+    use crate::vigenere::VigenereEngine;
+    use crate::{CaesarEngine, Cipher, Shift};
+
+    #[test]
+    fn round_trip() {
+        let engine = CaesarEngine::<IncompleteAscii>::new(Shift(17));
+        let message = ClearText::<IncompleteAscii>::try_new("Ave Imperator").unwrap();
+        let cipher = engine.encrypt(&message);
+
+        let json = serde_json::to_string(&(Shift(17), &cipher)).unwrap();
+        let (shift, loaded): (Shift, CipherText<IncompleteAscii>) =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(shift, Shift(17));
+        assert_eq!(loaded, cipher);
+
+        let json = serde_json::to_string(&Tagged(cipher)).unwrap();
+        let Tagged(loaded) =
+            serde_json::from_str::<Tagged<CipherText<IncompleteAscii>>>(&json).unwrap();
+        assert_eq!(engine.decrypt(&loaded), message);
+    }
+
+    #[test]
+    fn foreign_characters_round_trip() {
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+        for policy in [
+            ForeignCharPolicy::Passthrough,
+            ForeignCharPolicy::Replace('_'),
+        ] {
+            let message =
+                ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy("hello, world", policy)
+                    .unwrap();
+            let cipher = engine.encrypt(&message);
+
+            let json = serde_json::to_string(&cipher).unwrap();
+            assert!(serde_json::from_str::<CipherText<AsciiLowerCaseAlphabet>>(&json).is_err());
+
+            let json = serde_json::to_string(&Tagged(cipher)).unwrap();
+            let Tagged(loaded) =
+                serde_json::from_str::<Tagged<CipherText<AsciiLowerCaseAlphabet>>>(&json).unwrap();
+            assert_eq!(engine.decrypt(&loaded), message);
+        }
+
+        let error = serde_json::from_str::<Tagged<ClearText<AsciiLowerCaseAlphabet>>>(
+            r#"{"alphabet":"ascii-lowercase","text":"hello","policy":"drop"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("drop"));
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        let keys: [Key<IncompleteAscii>; 3] = [
+            CaesarEngine::new_preserving_case(Shift(-3)).into(),
+            VigenereEngine::try_new("Ave, Caesar!").unwrap().into(),
+            AffineEngine::try_new(7, 10).unwrap().into(),
+        ];
+        let message = ClearText::<IncompleteAscii>::try_new("Alea iacta est.").unwrap();
+        for key in keys {
+            let json = serde_json::to_string(&key).unwrap();
+            let loaded: Key<IncompleteAscii> = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded.to_string(), key.to_string());
+            assert_eq!(loaded.encrypt(&message), key.encrypt(&message));
+        }
+
+        let error = serde_json::from_str::<Key<AsciiLowerCaseAlphabet>>(
+            r#"{"alphabet":"incomplete-ascii","key":"caesar 3"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("incomplete-ascii"));
+        let error = serde_json::from_str::<Key<AsciiLowerCaseAlphabet>>(
+            r#"{"alphabet":"ascii-lowercase","key":"affine 2 3"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("NotCoprime"));
+    }
+
+    #[test]
+    fn deserialization_validates_letters() {
+        let error =
+            serde_json::from_str::<ClearText<AsciiLowerCaseAlphabet>>(r#""Hello""#).unwrap_err();
+        assert!(error.to_string().contains("'H'"));
+
+        let error = serde_json::from_str::<Tagged<CipherText<AsciiLowerCaseAlphabet>>>(
+            r#"{"alphabet":"incomplete-ascii","text":"hello"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("incomplete-ascii"));
+    }
+}
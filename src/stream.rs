@@ -0,0 +1,427 @@
+//! Encryption and decryption of streams of data
+//!
+//! [EncryptWriter] and [DecryptReader] apply a [CaesarEngine] to the UTF-8 bytes
+//! going through a [Write] or a [Read], so that large messages never have to be
+//! loaded in memory as a [ClearText](crate::ClearText) or a [CipherText](crate::CipherText).
+//!
+//! Errors of the engine are reported as [io::Error]s of kind [io::ErrorKind::InvalidData],
+//! wrapping a [StreamError] which gives the byte offset of the problem in the stream.
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::alphabets::Alphabet;
+use crate::{CaesarEngine, CharacterNotInAlphabet, ForeignCharPolicy};
+
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StreamError {
+    /// The character starting at the byte offset is not in the alphabet
+    NotInAlphabet { character: char, byte_offset: u64 },
+    /// The bytes starting at the byte offset are not valid UTF-8
+    InvalidUtf8 { byte_offset: u64 },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotInAlphabet {
+                character,
+                byte_offset,
+            } => write!(
+                f,
+                "character {character:?} at byte {byte_offset} is not in the alphabet"
+            ),
+            Self::InvalidUtf8 { byte_offset } => {
+                write!(f, "invalid UTF-8 at byte {byte_offset}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<StreamError> for io::Error {
+    fn from(error: StreamError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Decodes chunks of UTF-8 bytes and substitutes their characters,
+/// keeping the bytes of a character split between two chunks for the next one.
+///
+/// After an error, the transcoder is failed: the chunk was only partly processed,
+/// so every later call returns the same error instead of processing bytes again.
+#[derive(Debug)]
+struct Transcoder {
+    policy: ForeignCharPolicy,
+    /// Bytes received but not yet decoded
+    pending: Vec<u8>,
+    /// Offset in the stream of the first pending byte
+    byte_offset: u64,
+    /// The error that stopped the stream
+    error: Option<StreamError>,
+}
+
+impl Transcoder {
+    fn new() -> Self {
+        Self {
+            policy: ForeignCharPolicy::Reject,
+            pending: Vec::new(),
+            byte_offset: 0,
+            error: None,
+        }
+    }
+
+    fn transcode<F>(
+        &mut self,
+        input: &[u8],
+        substitute: F,
+        output: &mut String,
+    ) -> Result<(), StreamError>
+    where
+        F: Fn(char) -> Option<char>,
+    {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.transcode_chunk(input, substitute, output)
+            .inspect_err(|&error| self.error = Some(error))
+    }
+
+    fn transcode_chunk<F>(
+        &mut self,
+        input: &[u8],
+        substitute: F,
+        output: &mut String,
+    ) -> Result<(), StreamError>
+    where
+        F: Fn(char) -> Option<char>,
+    {
+        self.pending.extend_from_slice(input);
+        let text = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text,
+            Err(error) if error.error_len().is_none() => {
+                // The last character is incomplete, it will be decoded with the next chunk
+                std::str::from_utf8(&self.pending[..error.valid_up_to()]).unwrap()
+            }
+            Err(error) => {
+                return Err(StreamError::InvalidUtf8 {
+                    byte_offset: self.byte_offset + error.valid_up_to() as u64,
+                })
+            }
+        };
+
+        for (i, letter) in text.char_indices() {
+            if let Some(substituted_letter) = substitute(letter) {
+                output.push(substituted_letter);
+                continue;
+            }
+            // The position in the chunk is replaced by the offset in the whole stream
+            let foreign = CharacterNotInAlphabet::new(letter, 0, i);
+            let replacement =
+                self.policy
+                    .replacement(foreign)
+                    .map_err(|_| StreamError::NotInAlphabet {
+                        character: letter,
+                        byte_offset: self.byte_offset + i as u64,
+                    })?;
+            if let Some(replacement) = replacement {
+                output.push(substitute(replacement).unwrap_or(replacement));
+            }
+        }
+
+        let decoded_len = text.len();
+        self.byte_offset += decoded_len as u64;
+        self.pending.drain(..decoded_len);
+        Ok(())
+    }
+
+    /// Checks that the stream did not end in the middle of a character
+    fn finish(&self) -> Result<(), StreamError> {
+        if let Some(error) = self.error {
+            Err(error)
+        } else if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(StreamError::InvalidUtf8 {
+                byte_offset: self.byte_offset,
+            })
+        }
+    }
+}
+
+/// Writer that encrypts the text written to it, and writes the result
+/// to the inner writer.
+///
+/// Once everything is written, [EncryptWriter::finish] should be called to check
+/// that the text did not end in the middle of a UTF-8 character.
+///
+/// After a write fails because of the text, the writer keeps returning the
+/// same error: the failing write is not written at all, even partly.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+///
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::stream::EncryptWriter;
+/// use caesar_cipher::{CaesarEngine, ForeignCharPolicy, Shift};
+///
+/// let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(3));
+///
+/// let mut writer =
+///     EncryptWriter::new(&engine, Vec::new()).with_policy(ForeignCharPolicy::Passthrough);
+/// writer.write_all(b"hello\n").unwrap();
+/// writer.write_all(b"world\n").unwrap();
+/// let encrypted = writer.finish().unwrap();
+///
+/// assert_eq!(encrypted, b"khoor\nzruog\n");
+/// ```
+pub struct EncryptWriter<'e, A: Alphabet, W: Write> {
+    engine: &'e CaesarEngine<A>,
+    inner: W,
+    transcoder: Transcoder,
+    buffer: String,
+}
+
+impl<'e, A: Alphabet, W: Write> EncryptWriter<'e, A, W> {
+    /// Creates a writer encrypting with the engine, characters that are
+    /// not in the alphabet are rejected by default
+    pub fn new(engine: &'e CaesarEngine<A>, inner: W) -> Self {
+        Self {
+            engine,
+            inner,
+            transcoder: Transcoder::new(),
+            buffer: String::new(),
+        }
+    }
+
+    /// Sets how characters that are not in the alphabet are handled
+    pub fn with_policy(mut self, policy: ForeignCharPolicy) -> Self {
+        self.transcoder.policy = policy;
+        self
+    }
+
+    /// Flushes the inner writer and returns it
+    pub fn finish(mut self) -> io::Result<W> {
+        self.transcoder.finish()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<A: Alphabet, W: Write> Write for EncryptWriter<'_, A, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let engine = self.engine;
+        self.buffer.clear();
+        self.transcoder.transcode(
+            buf,
+            |letter| engine.substitution.encrypt_char(letter),
+            &mut self.buffer,
+        )?;
+        self.inner.write_all(self.buffer.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader that decrypts the text read from the inner reader.
+///
+/// After a read fails because of the text, the reader keeps returning the same error.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+///
+/// use caesar_cipher::alphabets::IncompleteAscii;
+/// use caesar_cipher::stream::DecryptReader;
+/// use caesar_cipher::{CaesarEngine, Shift};
+///
+/// let engine = CaesarEngine::<IncompleteAscii>::new(Shift(3));
+///
+/// let mut reader = DecryptReader::new(&engine, "Dyh'Lpshudwru".as_bytes());
+/// let mut decrypted = String::new();
+/// reader.read_to_string(&mut decrypted).unwrap();
+///
+/// assert_eq!(decrypted, "Ave Imperator");
+/// ```
+pub struct DecryptReader<'e, A: Alphabet, R: Read> {
+    engine: &'e CaesarEngine<A>,
+    inner: R,
+    transcoder: Transcoder,
+    input: Box<[u8]>,
+    /// Decrypted text not yet read
+    output: String,
+    output_pos: usize,
+    finished: bool,
+}
+
+impl<'e, A: Alphabet, R: Read> DecryptReader<'e, A, R> {
+    /// Creates a reader decrypting with the engine, characters that are
+    /// not in the alphabet are rejected by default
+    pub fn new(engine: &'e CaesarEngine<A>, inner: R) -> Self {
+        Self {
+            engine,
+            inner,
+            transcoder: Transcoder::new(),
+            input: vec![0; READ_BUFFER_SIZE].into_boxed_slice(),
+            output: String::new(),
+            output_pos: 0,
+            finished: false,
+        }
+    }
+
+    /// Sets how characters that are not in the alphabet are handled
+    pub fn with_policy(mut self, policy: ForeignCharPolicy) -> Self {
+        self.transcoder.policy = policy;
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<A: Alphabet, R: Read> Read for DecryptReader<'_, A, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output_pos == self.output.len() {
+            if self.finished {
+                return Ok(0);
+            }
+
+            self.output.clear();
+            self.output_pos = 0;
+            let read_len = self.inner.read(&mut self.input)?;
+            if read_len == 0 {
+                self.finished = true;
+                self.transcoder.finish()?;
+            } else {
+                let engine = self.engine;
+                self.transcoder.transcode(
+                    &self.input[..read_len],
+                    |letter| engine.substitution.decrypt_char(letter),
+                    &mut self.output,
+                )?;
+            }
+        }
+
+        let available = &self.output.as_bytes()[self.output_pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.output_pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::AsciiLowerCaseAlphabet;
+    use crate::Shift;
+
+    /// Reader returning at most one byte per read
+    struct ByteByByte<'a>(&'a [u8]);
+
+    impl Read for ByteByByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0
+                .take(1)
+                .read(buf)
+                .inspect(|&len| self.0 = &self.0[len..])
+        }
+    }
+
+    fn stream_error(error: io::Error) -> StreamError {
+        *error
+            .into_inner()
+            .unwrap()
+            .downcast::<StreamError>()
+            .unwrap()
+    }
+
+    #[test]
+    fn characters_split_between_writes() {
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(1));
+        let message = "café crème, déjà vu";
+
+        let mut writer =
+            EncryptWriter::new(&engine, Vec::new()).with_policy(ForeignCharPolicy::Passthrough);
+        for byte in message.as_bytes() {
+            writer.write_all(&[*byte]).unwrap();
+        }
+        let encrypted = writer.finish().unwrap();
+        assert_eq!(encrypted, "dbgé dsènf, eékà wv".as_bytes());
+
+        let mut reader = DecryptReader::new(&engine, ByteByByte(&encrypted))
+            .with_policy(ForeignCharPolicy::Passthrough);
+        let mut decrypted = String::new();
+        reader.read_to_string(&mut decrypted).unwrap();
+        assert_eq!(decrypted, message);
+    }
+
+    #[test]
+    fn errors_report_byte_offset() {
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(1));
+
+        let mut writer = EncryptWriter::new(&engine, Vec::new());
+        writer.write_all("déjà".as_bytes()[..2].as_ref()).unwrap();
+        let error = writer.write_all(&"déjà".as_bytes()[2..]).unwrap_err();
+        assert_eq!(
+            stream_error(error),
+            StreamError::NotInAlphabet {
+                character: 'é',
+                byte_offset: 1
+            }
+        );
+
+        let mut reader = DecryptReader::new(&engine, ByteByByte(b"abc\xffdef"));
+        let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(
+            stream_error(error),
+            StreamError::InvalidUtf8 { byte_offset: 3 }
+        );
+
+        let mut writer = EncryptWriter::new(&engine, Vec::new());
+        writer.write_all(&"abé".as_bytes()[..3]).unwrap();
+        let error = writer.finish().err().unwrap();
+        assert_eq!(
+            stream_error(error),
+            StreamError::InvalidUtf8 { byte_offset: 2 }
+        );
+    }
+
+    #[test]
+    fn streams_fail_after_an_error() {
+        let engine = CaesarEngine::<AsciiLowerCaseAlphabet>::new(Shift(1));
+        let not_in_alphabet = StreamError::NotInAlphabet {
+            character: ' ',
+            byte_offset: 5,
+        };
+
+        let mut output = Vec::new();
+        let mut writer = EncryptWriter::new(&engine, &mut output);
+        writer.write_all(b"hello").unwrap();
+        let error = writer.write_all(b" world").unwrap_err();
+        assert_eq!(stream_error(error), not_in_alphabet);
+        // The rest of the stream is refused, and nothing is written twice
+        let error = writer.write_all(b"again").unwrap_err();
+        assert_eq!(stream_error(error), not_in_alphabet);
+        let error = writer.finish().err().unwrap();
+        assert_eq!(stream_error(error), not_in_alphabet);
+        assert_eq!(output, b"ifmmp");
+
+        let mut reader = DecryptReader::new(&engine, ByteByByte(b"ifmmp xpsme"));
+        let mut decrypted = Vec::new();
+        let error = reader.read_to_end(&mut decrypted).unwrap_err();
+        assert_eq!(stream_error(error), not_in_alphabet);
+        let error = reader.read_to_end(&mut decrypted).unwrap_err();
+        assert_eq!(stream_error(error), not_in_alphabet);
+        assert_eq!(decrypted, b"hello");
+    }
+}
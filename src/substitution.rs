@@ -0,0 +1,274 @@
+//! General [monoalphabetic substitution](https://en.wikipedia.org/wiki/Substitution_cipher)
+//!
+//! In a monoalphabetic substitution each letter of the alphabet is always replaced
+//! by the same letter, the key is the permutation of the alphabet giving the
+//! replacement of each letter. The Caesar cipher is a substitution whose key is
+//! the rotated alphabet.
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::alphabets::Alphabet;
+use crate::rng::SplitMix64;
+use crate::table::{CharTable, Substitution};
+use crate::{CharacterNotInAlphabet, Cipher, CipherText, ClearText, ForeignCharPolicy};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum InvalidSubstitutionKey {
+    /// The key does not have as many letters as the alphabet
+    WrongLength { expected: usize, found: usize },
+    /// The key contains a character that is not in the alphabet
+    CharacterNotInAlphabet(CharacterNotInAlphabet),
+    /// The letter appears more than once in the key
+    DuplicateLetter(char),
+}
+
+impl From<CharacterNotInAlphabet> for InvalidSubstitutionKey {
+    fn from(error: CharacterNotInAlphabet) -> Self {
+        Self::CharacterNotInAlphabet(error)
+    }
+}
+
+/// Struct that encrypts and decrypts message with a monoalphabetic substitution.
+///
+/// The key is a permutation of the letters of the alphabet: the `i`-th letter of the
+/// alphabet is replaced by the `i`-th letter of the key.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::substitution::SubstitutionEngine;
+/// use caesar_cipher::ClearText;
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("fleeatonce").unwrap();
+///
+/// let engine = SubstitutionEngine::<AsciiLowerCaseAlphabet>::from_keyword("zebras").unwrap();
+/// assert_eq!(engine.key().iter().collect::<String>(), "zebrascdfghijklmnopqtuvwxy");
+///
+/// let encrypted_message = engine.encrypt(&message);
+/// assert_eq!(&encrypted_message, "siaazqlkba");
+/// assert_eq!(engine.decrypt(&encrypted_message), message);
+///
+/// let engine = SubstitutionEngine::<AsciiLowerCaseAlphabet>::atbash();
+/// assert_eq!(&engine.encrypt(&message), "uovvzglmxv");
+/// ```
+pub struct SubstitutionEngine<A: Alphabet> {
+    _marker: core::marker::PhantomData<A>,
+    key: Vec<char>,
+    substitution: Substitution,
+}
+
+impl<A: Alphabet> SubstitutionEngine<A> {
+    /// Creates a new engine for the key, which must be a permutation
+    /// of the letters of the alphabet
+    pub fn try_new<T: ToString>(key: T) -> Result<Self, InvalidSubstitutionKey> {
+        Self::try_from_letters(key.to_string().chars())
+    }
+
+    /// Creates a new engine for the key given as an iterator of letters
+    pub fn try_from_letters<I>(key: I) -> Result<Self, InvalidSubstitutionKey>
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let alphabet = A::letters();
+        let key = key.into_iter().collect::<Vec<_>>();
+        if key.len() != alphabet.len() {
+            return Err(InvalidSubstitutionKey::WrongLength {
+                expected: alphabet.len(),
+                found: key.len(),
+            });
+        }
+
+        let letter_indices = CharTable::new(alphabet.iter().copied().zip(0..));
+        let mut seen = vec![false; alphabet.len()];
+        let mut byte_offset = 0;
+        for (index, &letter) in key.iter().enumerate() {
+            let letter_index = letter_indices
+                .get(letter)
+                .ok_or(CharacterNotInAlphabet::new(letter, index, byte_offset))?;
+            byte_offset += letter.len_utf8();
+            if core::mem::replace(&mut seen[letter_index], true) {
+                return Err(InvalidSubstitutionKey::DuplicateLetter(letter));
+            }
+        }
+
+        Ok(Self::from_permutation(key))
+    }
+
+    /// Creates a new engine whose key starts with the letters of the keyword,
+    /// without repetitions, followed by the other letters of the alphabet in order
+    pub fn from_keyword<T: ToString>(keyword: T) -> Result<Self, InvalidSubstitutionKey> {
+        let alphabet = A::letters();
+        let mut key = Vec::with_capacity(alphabet.len());
+        for (index, (byte_offset, letter)) in keyword.to_string().char_indices().enumerate() {
+            if !alphabet.contains(&letter) {
+                return Err(CharacterNotInAlphabet::new(letter, index, byte_offset).into());
+            }
+            if !key.contains(&letter) {
+                key.push(letter);
+            }
+        }
+        for letter in alphabet {
+            if !key.contains(letter) {
+                key.push(*letter);
+            }
+        }
+
+        Ok(Self::from_permutation(key))
+    }
+
+    /// Creates the [Atbash](https://en.wikipedia.org/wiki/Atbash) engine,
+    /// whose key is the reversed alphabet
+    pub fn atbash() -> Self {
+        Self::from_permutation(A::letters().iter().rev().copied().collect())
+    }
+
+    /// Creates an engine with a random key, the same seed always gives the same key
+    ///
+    /// The generator is not cryptographically secure.
+    pub fn random(seed: u64) -> Self {
+        let mut key = A::letters().to_vec();
+        SplitMix64::new(seed).shuffle(&mut key);
+        Self::from_permutation(key)
+    }
+
+    /// The key must be a permutation of the alphabet
+    pub(crate) fn from_permutation(key: Vec<char>) -> Self {
+        Self {
+            _marker: Default::default(),
+            substitution: Substitution::new(A::letters(), &key),
+            key,
+        }
+    }
+
+    /// The replacement of each letter of the alphabet
+    pub fn key(&self) -> &[char] {
+        &self.key
+    }
+
+    /// Encrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
+    pub fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        CipherText {
+            _marker: Default::default(),
+            cipher: self
+                .substitution
+                .encrypt(&clear_message.message, ForeignCharPolicy::Passthrough)
+                .expect("passing characters through cannot fail"),
+        }
+    }
+
+    /// Decrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
+    pub fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        ClearText {
+            _marker: Default::default(),
+            message: self
+                .substitution
+                .decrypt(&cipher_message.cipher, ForeignCharPolicy::Passthrough)
+                .expect("passing characters through cannot fail"),
+        }
+    }
+
+    /// Encrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_encrypt(
+        &self,
+        clear_message: &ClearText<A>,
+    ) -> Result<CipherText<A>, CharacterNotInAlphabet> {
+        Ok(CipherText {
+            _marker: Default::default(),
+            cipher: self
+                .substitution
+                .encrypt(&clear_message.message, ForeignCharPolicy::Reject)?,
+        })
+    }
+
+    /// Decrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_decrypt(
+        &self,
+        cipher_message: &CipherText<A>,
+    ) -> Result<ClearText<A>, CharacterNotInAlphabet> {
+        Ok(ClearText {
+            _marker: Default::default(),
+            message: self
+                .substitution
+                .decrypt(&cipher_message.cipher, ForeignCharPolicy::Reject)?,
+        })
+    }
+}
+
+impl<A: Alphabet> Cipher<A> for SubstitutionEngine<A> {
+    fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        SubstitutionEngine::encrypt(self, clear_message)
+    }
+
+    fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        SubstitutionEngine::decrypt(self, cipher_message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+
+    #[test]
+    fn invalid_keys() {
+        type Engine = SubstitutionEngine<AsciiLowerCaseAlphabet>;
+        assert_eq!(
+            Engine::try_new("abc").err(),
+            Some(InvalidSubstitutionKey::WrongLength {
+                expected: 26,
+                found: 3
+            })
+        );
+        assert_eq!(
+            Engine::try_new("zyxwvutsrqponmlkjihgfedcbZ").err(),
+            Some(InvalidSubstitutionKey::CharacterNotInAlphabet(
+                CharacterNotInAlphabet::new('Z', 25, 25)
+            ))
+        );
+        assert_eq!(
+            Engine::try_new("zyxwvutsrqponmlkjihgfedcbz").err(),
+            Some(InvalidSubstitutionKey::DuplicateLetter('z'))
+        );
+        assert_eq!(
+            Engine::from_keyword("Zebras").err(),
+            Some(InvalidSubstitutionKey::CharacterNotInAlphabet(
+                CharacterNotInAlphabet::new('Z', 0, 0)
+            ))
+        );
+    }
+
+    #[test]
+    fn random_keys_are_permutations() {
+        let message =
+            ClearText::<IncompleteAscii>::try_new("Ave Imperator, morituri te salutant").unwrap();
+        for seed in 0..10 {
+            let engine = SubstitutionEngine::<IncompleteAscii>::random(seed);
+            let same_key = SubstitutionEngine::<IncompleteAscii>::try_from_letters(
+                engine.key().iter().copied(),
+            )
+            .unwrap();
+            assert_eq!(same_key.key(), engine.key());
+            assert_eq!(
+                SubstitutionEngine::<IncompleteAscii>::random(seed).key(),
+                engine.key()
+            );
+
+            let encrypted_message = engine.try_encrypt(&message).unwrap();
+            assert_eq!(engine.try_decrypt(&encrypted_message).unwrap(), message);
+        }
+        assert_ne!(
+            SubstitutionEngine::<IncompleteAscii>::random(0).key(),
+            SubstitutionEngine::<IncompleteAscii>::random(1).key()
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::{CharacterNotInAlphabet, ForeignCharPolicy, Shift};
+
+/// Lookup table from a letter of an alphabet to a value.
+///
+/// Alphabets made only of ascii characters are stored in a dense array
+/// indexed by the character code, for a constant time lookup. Other alphabets
+/// are stored in a table sorted by character, which is searched by dichotomy
+/// in logarithmic time.
+#[derive(Debug, Clone)]
+pub(crate) enum CharTable<T> {
+    Ascii(Box<[Option<T>; 128]>),
+    Sorted(Vec<(char, T)>),
+}
+
+impl<T: Copy> CharTable<T> {
+    pub(crate) fn new<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (char, T)>,
+    {
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        if entries.iter().all(|(c, _)| c.is_ascii()) {
+            let mut table = Box::new([None; 128]);
+            for (c, value) in entries {
+                table[c as usize] = Some(value);
+            }
+            Self::Ascii(table)
+        } else {
+            entries.sort_by_key(|(c, _)| *c);
+            Self::Sorted(entries)
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, c: char) -> Option<T> {
+        match self {
+            Self::Ascii(table) => table.get(c as usize).copied().flatten(),
+            Self::Sorted(entries) => entries
+                .binary_search_by_key(&c, |(letter, _)| *letter)
+                .ok()
+                .map(|i| entries[i].1),
+        }
+    }
+}
+
+/// The set of the letters of an alphabet, in the list of the sets built so far
+struct LetterSet {
+    alphabet: &'static [char],
+    letters: CharTable<()>,
+    next: *const LetterSet,
+}
+
+/// Last set added to the list, the sets are never removed nor modified once added
+static LETTER_SETS: AtomicPtr<LetterSet> = AtomicPtr::new(ptr::null_mut());
+
+impl CharTable<()> {
+    /// The set of the letters of an alphabet
+    ///
+    /// The set is built the first time the alphabet is used, and kept for the rest
+    /// of the program. Alphabets are few, so they are looked up in a list.
+    pub(crate) fn letters(alphabet: &'static [char]) -> &'static Self {
+        let mut head = LETTER_SETS.load(Ordering::Acquire);
+        let mut node = head.cast_const();
+        // SAFETY: the nodes of the list are leaked boxes that are never freed nor
+        // modified after being published by the `compare_exchange` below
+        while let Some(set) = unsafe { node.as_ref() } {
+            if ptr::eq(set.alphabet, alphabet) || set.alphabet == alphabet {
+                return &set.letters;
+            }
+            node = set.next;
+        }
+
+        let set = Box::into_raw(Box::new(LetterSet {
+            alphabet,
+            letters: Self::new(alphabet.iter().map(|&c| (c, ()))),
+            next: head,
+        }));
+        // Another thread may add the same alphabet meanwhile, both sets are then
+        // kept in the list, which is harmless
+        while let Err(current) =
+            LETTER_SETS.compare_exchange(head, set, Ordering::AcqRel, Ordering::Acquire)
+        {
+            head = current;
+            // SAFETY: the set is not published yet, so this thread is its only user
+            unsafe { (*set).next = head };
+        }
+        // SAFETY: the set is published and will never be freed nor modified
+        unsafe { &(*set).letters }
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, c: char) -> bool {
+        self.get(c).is_some()
+    }
+}
+
+/// Encryption and decryption tables of a monoalphabetic substitution,
+/// that is, a cipher where each letter of the alphabet is always replaced
+/// by the same letter.
+#[derive(Debug, Clone)]
+pub(crate) struct Substitution {
+    /// Maps each letter of the alphabet to its encrypted letter
+    encryption: CharTable<char>,
+    /// Maps each encrypted letter back to the letter of the alphabet
+    decryption: CharTable<char>,
+    /// Whether each letter is replaced by a letter with the same UTF-8 length
+    same_width: bool,
+}
+
+impl Substitution {
+    /// Creates the tables replacing `alphabet[i]` by `substituted[i]`
+    ///
+    /// `substituted` must be a permutation of `alphabet`.
+    pub(crate) fn new(alphabet: &[char], substituted: &[char]) -> Self {
+        debug_assert_eq!(alphabet.len(), substituted.len());
+        let pairs = || alphabet.iter().copied().zip(substituted.iter().copied());
+        Self::from_tables(
+            CharTable::new(pairs()),
+            CharTable::new(pairs().map(|(clear, cipher)| (cipher, clear))),
+        )
+    }
+
+    fn from_tables(encryption: CharTable<char>, decryption: CharTable<char>) -> Self {
+        let same_width = match &encryption {
+            // Letters of an ascii table are only replaced by letters of the same alphabet
+            CharTable::Ascii(_) => true,
+            CharTable::Sorted(entries) => entries
+                .iter()
+                .all(|(clear, cipher)| clear.len_utf8() == cipher.len_utf8()),
+        };
+        Self {
+            encryption,
+            decryption,
+            same_width,
+        }
+    }
+
+    /// Creates the tables replacing each letter by the one `shift` positions after it
+    pub(crate) fn shift(alphabet: &[char], shift: Shift) -> Self {
+        let mut shifted_alphabet = alphabet.to_vec();
+        shifted_alphabet.rotate_left(shift.reduce(alphabet.len()));
+        Self::new(alphabet, &shifted_alphabet)
+    }
+
+    /// Creates the tables of a shift that ignores the case of the letters
+    ///
+    /// The shift is done in the alphabet folded to lowercase, then the encryption
+    /// and decryption of uppercase letters restores their case. Letters shifted onto
+    /// a character without case (e.g. a symbol) cannot have their case restored
+    /// and are decrypted in lowercase.
+    ///
+    /// When several letters have the same uppercase form, like `'σ'` and `'ς'` which
+    /// are both `'Σ'`, only the lowercase form of the uppercase letter has a case:
+    /// the others are treated like characters without case.
+    pub(crate) fn shift_preserving_case(alphabet: &[char], shift: Shift) -> Self {
+        let mut folded_alphabet = Vec::with_capacity(alphabet.len());
+        for letter in alphabet.iter().map(|&c| to_lowercase(c).unwrap_or(c)) {
+            if !folded_alphabet.contains(&letter) {
+                folded_alphabet.push(letter);
+            }
+        }
+        let mut shifted_alphabet = folded_alphabet.clone();
+        shifted_alphabet.rotate_left(shift.reduce(folded_alphabet.len()));
+
+        let mut encryption = Vec::with_capacity(2 * folded_alphabet.len());
+        let mut decryption = Vec::with_capacity(2 * folded_alphabet.len());
+        for (&clear, &cipher) in folded_alphabet.iter().zip(shifted_alphabet.iter()) {
+            encryption.push((clear, cipher));
+            decryption.push((cipher, clear));
+            let Some(upper_clear) = cased_uppercase(clear) else {
+                continue;
+            };
+            match cased_uppercase(cipher) {
+                Some(upper_cipher) => {
+                    encryption.push((upper_clear, upper_cipher));
+                    decryption.push((upper_cipher, upper_clear));
+                }
+                None => encryption.push((upper_clear, cipher)),
+            }
+        }
+
+        Self::from_tables(CharTable::new(encryption), CharTable::new(decryption))
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn encrypt_char(&self, letter: char) -> Option<char> {
+        self.encryption.get(letter)
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn decrypt_char(&self, letter: char) -> Option<char> {
+        self.decryption.get(letter)
+    }
+
+    pub(crate) fn encrypt(
+        &self,
+        message: &str,
+        policy: ForeignCharPolicy,
+    ) -> Result<String, CharacterNotInAlphabet> {
+        Self::apply(&self.encryption, message, policy)
+    }
+
+    pub(crate) fn decrypt(
+        &self,
+        cipher: &str,
+        policy: ForeignCharPolicy,
+    ) -> Result<String, CharacterNotInAlphabet> {
+        Self::apply(&self.decryption, cipher, policy)
+    }
+
+    /// Encrypts the message at the end of `output`, keeping characters
+    /// that are not in the alphabet
+    pub(crate) fn encrypt_into(&self, message: &str, output: &mut String) {
+        Self::apply_into(
+            &self.encryption,
+            message,
+            ForeignCharPolicy::Passthrough,
+            output,
+        )
+        .expect("passing characters through cannot fail")
+    }
+
+    /// Decrypts the message at the end of `output`, keeping characters
+    /// that are not in the alphabet
+    pub(crate) fn decrypt_into(&self, cipher: &str, output: &mut String) {
+        Self::apply_into(
+            &self.decryption,
+            cipher,
+            ForeignCharPolicy::Passthrough,
+            output,
+        )
+        .expect("passing characters through cannot fail")
+    }
+
+    /// Encrypts the message, in place when every letter is replaced
+    /// by a letter of the same UTF-8 length
+    pub(crate) fn encrypt_in_place(&self, message: String) -> String {
+        self.apply_in_place(&self.encryption, message)
+    }
+
+    /// Decrypts the message, in place when every letter is replaced
+    /// by a letter of the same UTF-8 length
+    pub(crate) fn decrypt_in_place(&self, cipher: String) -> String {
+        self.apply_in_place(&self.decryption, cipher)
+    }
+
+    /// Encrypts the ascii letters of the bytes in place, returns `false`
+    /// without changing them if the alphabet is not made of ascii characters
+    pub(crate) fn encrypt_bytes(&self, bytes: &mut [u8]) -> bool {
+        Self::apply_bytes(&self.encryption, bytes)
+    }
+
+    /// Decrypts the ascii letters of the bytes in place, returns `false`
+    /// without changing them if the alphabet is not made of ascii characters
+    pub(crate) fn decrypt_bytes(&self, bytes: &mut [u8]) -> bool {
+        Self::apply_bytes(&self.decryption, bytes)
+    }
+
+    /// Substitutes each letter of the input using the table, characters
+    /// that are not in the table are handled according to the `policy`
+    fn apply(
+        table: &CharTable<char>,
+        input: &str,
+        policy: ForeignCharPolicy,
+    ) -> Result<String, CharacterNotInAlphabet> {
+        let mut output = String::with_capacity(input.len());
+        Self::apply_into(table, input, policy, &mut output)?;
+        Ok(output)
+    }
+
+    fn apply_into(
+        table: &CharTable<char>,
+        input: &str,
+        policy: ForeignCharPolicy,
+        output: &mut String,
+    ) -> Result<(), CharacterNotInAlphabet> {
+        output.reserve(input.len());
+        for (index, (byte_offset, letter)) in input.char_indices().enumerate() {
+            if let Some(substituted_letter) = table.get(letter) {
+                output.push(substituted_letter);
+            } else if let Some(replacement) =
+                policy.replacement(CharacterNotInAlphabet::new(letter, index, byte_offset))?
+            {
+                output.push(table.get(replacement).unwrap_or(replacement));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_in_place(&self, table: &CharTable<char>, input: String) -> String {
+        if !self.same_width {
+            let output = Self::apply(table, &input, ForeignCharPolicy::Passthrough);
+            return output.expect("passing characters through cannot fail");
+        }
+
+        let mut bytes = input.into_bytes();
+        let mut byte_offset = 0;
+        while byte_offset < bytes.len() {
+            let width = utf8_width(bytes[byte_offset]);
+            let character = &mut bytes[byte_offset..byte_offset + width];
+            let letter = core::str::from_utf8(character)
+                .ok()
+                .and_then(|letter| letter.chars().next())
+                .expect("the bytes come from a string");
+            if let Some(substituted_letter) = table.get(letter) {
+                substituted_letter.encode_utf8(character);
+            }
+            byte_offset += width;
+        }
+        String::from_utf8(bytes).expect("letters are replaced by letters of the same width")
+    }
+
+    fn apply_bytes(table: &CharTable<char>, bytes: &mut [u8]) -> bool {
+        let CharTable::Ascii(table) = table else {
+            return false;
+        };
+        for byte in bytes {
+            // Bytes of non ascii characters are out of the table, and are left unchanged
+            if let Some(Some(substituted_letter)) = table.get(*byte as usize) {
+                *byte = *substituted_letter as u8;
+            }
+        }
+        true
+    }
+}
+
+/// Number of bytes of the UTF-8 character starting with the byte
+fn utf8_width(first_byte: u8) -> usize {
+    match first_byte {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    }
+}
+
+/// Lowercase form of the character, if it is a single character
+fn to_lowercase(c: char) -> Option<char> {
+    let mut lowercase = c.to_lowercase();
+    lowercase.next().filter(|_| lowercase.next().is_none())
+}
+
+/// Uppercase form of the character, if it is a single character
+fn to_uppercase(c: char) -> Option<char> {
+    let mut uppercase = c.to_uppercase();
+    uppercase.next().filter(|_| uppercase.next().is_none())
+}
+
+/// Uppercase form of a lowercase letter, if the letter is the lowercase form of it,
+/// so that each uppercase letter is the uppercase form of a single letter
+fn cased_uppercase(c: char) -> Option<char> {
+    to_uppercase(c).filter(|&upper| upper != c && to_lowercase(upper) == Some(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::{
+        Alphabet, ArabicAlphabet, GreekAlphabet, HebrewAlphabet, IncompleteAscii, RussianAlphabet,
+    };
+
+    #[test]
+    fn letter_sets_are_built_once() {
+        let greek = CharTable::letters(GreekAlphabet::letters());
+        assert!(greek.contains('ω'));
+        assert!(!greek.contains('w'));
+        assert!(ptr::eq(greek, CharTable::letters(GreekAlphabet::letters())));
+
+        let ascii = CharTable::letters(IncompleteAscii::letters());
+        assert!(ascii.contains(' '));
+        assert!(!ptr::eq(greek, ascii));
+        assert!(ptr::eq(
+            ascii,
+            CharTable::letters(IncompleteAscii::letters())
+        ));
+    }
+
+    #[test]
+    fn ascii_and_unicode_tables() {
+        let ascii = CharTable::new([('a', 0), ('z', 25)]);
+        assert!(matches!(ascii, CharTable::Ascii(_)));
+        assert_eq!(ascii.get('z'), Some(25));
+        assert_eq!(ascii.get('b'), None);
+        assert_eq!(ascii.get('é'), None);
+
+        let unicode = CharTable::new([('ω', 2), ('α', 0), ('a', 1)]);
+        assert!(matches!(unicode, CharTable::Sorted(_)));
+        assert_eq!(unicode.get('α'), Some(0));
+        assert_eq!(unicode.get('ω'), Some(2));
+        assert_eq!(unicode.get('a'), Some(1));
+        assert_eq!(unicode.get('b'), None);
+    }
+
+    #[test]
+    fn in_place_substitution() {
+        let same_width = Substitution::shift(&['α', 'β', 'γ'], Shift(1));
+        assert!(same_width.same_width);
+        assert_eq!(same_width.encrypt_in_place("αβ γ".into()), "βγ α");
+        assert!(!same_width.encrypt_bytes(&mut []));
+
+        // Letters of different widths cannot be replaced in place
+        let mixed_width = Substitution::shift(&['a', 'é', '€'], Shift(1));
+        assert!(!mixed_width.same_width);
+        assert_eq!(mixed_width.encrypt_in_place("a é€".into()), "é €a");
+        assert_eq!(mixed_width.decrypt_in_place("é €a".into()), "a é€");
+    }
+
+    fn case_preserving_round_trip<A: Alphabet>() {
+        let lowercase = A::letters().iter().collect::<String>();
+        let message = lowercase.clone() + &lowercase.to_uppercase();
+        for shift in 0..A::letters().len() as isize {
+            let substitution = Substitution::shift_preserving_case(A::letters(), Shift(shift));
+            let encrypted = substitution
+                .encrypt(&message, ForeignCharPolicy::Passthrough)
+                .unwrap();
+            let decrypted = substitution
+                .decrypt(&encrypted, ForeignCharPolicy::Passthrough)
+                .unwrap();
+            // Letters shifted onto a letter without case are decrypted in lowercase
+            assert_eq!(
+                decrypted.to_lowercase(),
+                message.to_lowercase(),
+                "shift {shift}"
+            );
+            assert_eq!(decrypted[..lowercase.len()], lowercase, "shift {shift}");
+            for (clear, decrypted) in message.chars().zip(decrypted.chars()) {
+                assert!(clear == decrypted || clear.is_uppercase(), "shift {shift}");
+            }
+        }
+    }
+
+    #[test]
+    fn case_preserving_round_trip_in_unicode_alphabets() {
+        case_preserving_round_trip::<GreekAlphabet>();
+        case_preserving_round_trip::<RussianAlphabet>();
+        case_preserving_round_trip::<HebrewAlphabet>();
+        case_preserving_round_trip::<ArabicAlphabet>();
+
+        let substitution = Substitution::shift_preserving_case(GreekAlphabet::letters(), Shift(3));
+        let encrypted = substitution
+            .encrypt("ΣΟΦΙΑ σοφος", ForeignCharPolicy::Passthrough)
+            .unwrap();
+        let decrypted = substitution
+            .decrypt(&encrypted, ForeignCharPolicy::Passthrough)
+            .unwrap();
+        assert_eq!(decrypted, "ΣοΦΙΑ σοφος", "'ο' is shifted onto 'ς'");
+    }
+}
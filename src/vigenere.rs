@@ -0,0 +1,288 @@
+//! The [Vigenère cipher](https://en.wikipedia.org/wiki/Vigen%C3%A8re_cipher)
+//!
+//! The Vigenère cipher is a polyalphabetic cipher: instead of shifting all the letters
+//! of the message by the same number, each letter is shifted by the position in the
+//! alphabet of the corresponding letter of a keyword, which is repeated over the message.
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::alphabets::Alphabet;
+use crate::table::CharTable;
+use crate::{CharacterNotInAlphabet, Cipher, CipherText, ClearText, ForeignCharPolicy};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum InvalidKeyword {
+    /// The keyword has no letters
+    Empty,
+    /// The keyword contains a character that is not in the alphabet
+    CharacterNotInAlphabet(CharacterNotInAlphabet),
+}
+
+impl From<CharacterNotInAlphabet> for InvalidKeyword {
+    fn from(error: CharacterNotInAlphabet) -> Self {
+        Self::CharacterNotInAlphabet(error)
+    }
+}
+
+impl core::fmt::Display for InvalidKeyword {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the keyword has no letters"),
+            Self::CharacterNotInAlphabet(error) => write!(f, "invalid keyword: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for InvalidKeyword {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Empty => None,
+            Self::CharacterNotInAlphabet(error) => Some(error),
+        }
+    }
+}
+
+/// Struct that encrypts and decrypts message with the Vigenère cipher.
+///
+/// As the [CaesarEngine](crate::CaesarEngine), a `VigenereEngine` is tied to an [Alphabet],
+/// the keyword must be written in this alphabet, and the engine only
+/// encrypts / decrypts messages that are in the same `Alphabet`.
+///
+/// Characters that are not in the alphabet (kept by a [ForeignCharPolicy]) are
+/// left unchanged and do not consume a letter of the keyword.
+///
+/// # Examples
+///
+/// ```
+/// use caesar_cipher::alphabets::AsciiLowerCaseAlphabet;
+/// use caesar_cipher::vigenere::VigenereEngine;
+/// use caesar_cipher::ClearText;
+///
+/// let message = ClearText::<AsciiLowerCaseAlphabet>::try_new("attackatdawn").unwrap();
+///
+/// let engine = VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("lemon").unwrap();
+///
+/// let encrypted_message = engine.encrypt(&message);
+/// assert_eq!(&encrypted_message, "lxfopvefrnhr");
+///
+/// let decrypted_message = engine.decrypt(&encrypted_message);
+/// assert_eq!(decrypted_message, message);
+/// ```
+///
+/// Trying to mix [Alphabet]s will create a compile error
+/// ```compile_fail
+/// use caesar_cipher::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+/// use caesar_cipher::vigenere::VigenereEngine;
+/// use caesar_cipher::ClearText;
+///
+/// let message = ClearText::<IncompleteAscii>::try_new("Ave Imperator, morituri te salutant").unwrap();
+///
+/// let engine = VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("lemon").unwrap();
+///
+/// // This line make compilation fail
+/// let encrypted_message = engine.encrypt(&message);
+/// ```
+pub struct VigenereEngine<A: Alphabet> {
+    _marker: core::marker::PhantomData<A>,
+    /// Position of each letter in the alphabet
+    letter_indices: CharTable<usize>,
+    /// Shift applied by each letter of the keyword
+    key_shifts: Vec<usize>,
+}
+
+impl<A: Alphabet> VigenereEngine<A> {
+    /// Creates a new engine for the given keyword
+    pub fn try_new<T: ToString>(keyword: T) -> Result<Self, InvalidKeyword> {
+        let letter_indices = CharTable::new(A::letters().iter().copied().zip(0..));
+        let key_shifts = keyword
+            .to_string()
+            .char_indices()
+            .enumerate()
+            .map(|(index, (byte_offset, letter))| {
+                letter_indices
+                    .get(letter)
+                    .ok_or(CharacterNotInAlphabet::new(letter, index, byte_offset))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if key_shifts.is_empty() {
+            return Err(InvalidKeyword::Empty);
+        }
+
+        Ok(Self {
+            _marker: Default::default(),
+            letter_indices,
+            key_shifts,
+        })
+    }
+
+    /// The keyword the engine was created with
+    pub fn keyword(&self) -> String {
+        self.key_shifts
+            .iter()
+            .map(|&shift| A::letters()[shift])
+            .collect()
+    }
+
+    /// Encrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
+    pub fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        CipherText {
+            _marker: Default::default(),
+            cipher: self
+                .apply(
+                    &clear_message.message,
+                    ForeignCharPolicy::Passthrough,
+                    false,
+                )
+                .expect("passing characters through cannot fail"),
+        }
+    }
+
+    /// Decrypts the message
+    ///
+    /// Characters that are not in the alphabet (kept by a [ForeignCharPolicy])
+    /// are left unchanged.
+    pub fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        ClearText {
+            _marker: Default::default(),
+            message: self
+                .apply(&cipher_message.cipher, ForeignCharPolicy::Passthrough, true)
+                .expect("passing characters through cannot fail"),
+        }
+    }
+
+    /// Encrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_encrypt(
+        &self,
+        clear_message: &ClearText<A>,
+    ) -> Result<CipherText<A>, CharacterNotInAlphabet> {
+        Ok(CipherText {
+            _marker: Default::default(),
+            cipher: self.apply(&clear_message.message, ForeignCharPolicy::Reject, false)?,
+        })
+    }
+
+    /// Decrypts the message, returning an error
+    /// if it contains a character that is not in the alphabet.
+    pub fn try_decrypt(
+        &self,
+        cipher_message: &CipherText<A>,
+    ) -> Result<ClearText<A>, CharacterNotInAlphabet> {
+        Ok(ClearText {
+            _marker: Default::default(),
+            message: self.apply(&cipher_message.cipher, ForeignCharPolicy::Reject, true)?,
+        })
+    }
+
+    /// Shifts each letter of the input by the next letter of the keyword,
+    /// backward when `decrypt` is set
+    fn apply(
+        &self,
+        input: &str,
+        policy: ForeignCharPolicy,
+        decrypt: bool,
+    ) -> Result<String, CharacterNotInAlphabet> {
+        let alphabet = A::letters();
+        let mut key_shifts = self.key_shifts.iter().cycle();
+        let mut output = String::with_capacity(input.len());
+        for (index, (byte_offset, letter)) in input.char_indices().enumerate() {
+            let letter = match self.letter_indices.get(letter) {
+                Some(_) => letter,
+                None => match policy.replacement(CharacterNotInAlphabet::new(
+                    letter,
+                    index,
+                    byte_offset,
+                ))? {
+                    Some(replacement) => replacement,
+                    None => continue,
+                },
+            };
+            match self.letter_indices.get(letter) {
+                Some(letter_index) => {
+                    let shift = *key_shifts.next().unwrap();
+                    let shifted_index = if decrypt {
+                        letter_index + alphabet.len() - shift
+                    } else {
+                        letter_index + shift
+                    };
+                    output.push(alphabet[shifted_index % alphabet.len()]);
+                }
+                None => output.push(letter),
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl<A: Alphabet> Cipher<A> for VigenereEngine<A> {
+    fn encrypt(&self, clear_message: &ClearText<A>) -> CipherText<A> {
+        VigenereEngine::encrypt(self, clear_message)
+    }
+
+    fn decrypt(&self, cipher_message: &CipherText<A>) -> ClearText<A> {
+        VigenereEngine::decrypt(self, cipher_message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::{AsciiLowerCaseAlphabet, IncompleteAscii};
+
+    #[test]
+    fn invalid_keywords() {
+        assert!(matches!(
+            VigenereEngine::<AsciiLowerCaseAlphabet>::try_new(""),
+            Err(InvalidKeyword::Empty)
+        ));
+        assert!(matches!(
+            VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("Lemon"),
+            Err(InvalidKeyword::CharacterNotInAlphabet(
+                CharacterNotInAlphabet { character: 'L', .. }
+            ))
+        ));
+
+        let Err(error) = VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("Lemon") else {
+            panic!("'L' is not in the alphabet");
+        };
+        let error: Box<dyn core::error::Error> = Box::new(error);
+        assert_eq!(
+            error.to_string(),
+            "invalid keyword: character 'L' at index 0 is not in the alphabet"
+        );
+        assert!(error.source().is_some());
+        assert_eq!(
+            InvalidKeyword::Empty.to_string(),
+            "the keyword has no letters"
+        );
+    }
+
+    #[test]
+    fn foreign_characters_do_not_consume_keyword() {
+        let engine = VigenereEngine::<AsciiLowerCaseAlphabet>::try_new("lemon").unwrap();
+        let message = ClearText::<AsciiLowerCaseAlphabet>::try_new_with_policy(
+            "attack at dawn!",
+            ForeignCharPolicy::Passthrough,
+        )
+        .unwrap();
+
+        let encrypted_message = engine.encrypt(&message);
+        assert_eq!(&encrypted_message, "lxfopv ef rnhr!");
+        assert_eq!(engine.decrypt(&encrypted_message), message);
+    }
+
+    #[test]
+    fn complete_sentence() {
+        let engine = VigenereEngine::<IncompleteAscii>::try_new("Caesar").unwrap();
+        let message = ClearText::<IncompleteAscii>::try_new(
+            "Ave Imperator, morituri te salutant, veni, vidi, vici.",
+        )
+        .unwrap();
+
+        let encrypted_message = engine.try_encrypt(&message).unwrap();
+        assert_eq!(engine.try_decrypt(&encrypted_message).unwrap(), message);
+    }
+}